@@ -0,0 +1,692 @@
+//! Headless command-line front end for the typing engine.
+//!
+//! ```text
+//! ghostkeys-cli type --file notes.md --profile fast --countdown 5
+//! ghostkeys-cli type --file letter.txt --var name=Ada --var company="Acme Ltd"
+//! ghostkeys-cli type --clipboard --countdown 3
+//! ghostkeys-cli preview --file notes.md
+//! ghostkeys-cli plan --file notes.md --out plan.json
+//! ghostkeys-cli stats --file notes.md --runs 20
+//! ghostkeys-cli calibrate --recording keys.jsonl --reference test.txt --out me.json
+//! ```
+//!
+//! Built with `--no-default-features`, it does not link Tauri:
+//! `cargo build --bin ghostkeys-cli --no-default-features`.
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use ghostkeys_lib::content::normalize::{self, NormalizationReport};
+use ghostkeys_lib::content::{self, formats, pdf, template, Document};
+use ghostkeys_lib::typer::calibrate;
+use ghostkeys_lib::typer::distribution::IntervalDistribution;
+use ghostkeys_lib::typer::events::EventSink;
+use ghostkeys_lib::typer::plan::{plan_content, Chunk, PlanSummary};
+use ghostkeys_lib::typer::stats::{self, LogFormat};
+use ghostkeys_lib::typer::timing::PauseBreakdown;
+use ghostkeys_lib::typer::trigger::StartTrigger;
+use ghostkeys_lib::typer::TypingEngine;
+use ghostkeys_lib::{Config, TypingError, CLIPBOARD_NAME};
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+/// Exit codes (clap itself exits with 2 on usage errors)
+const EXIT_FAILURE: u8 = 1;
+const EXIT_EMPTY_CONTENT: u8 = 3;
+const EXIT_IO: u8 = 4;
+const EXIT_KEYBOARD: u8 = 5;
+const EXIT_INTERRUPTED: u8 = 130;
+
+#[derive(Parser)]
+#[command(name = "ghostkeys-cli", version, about = "Human typing simulator (headless)")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Type the content into the focused window
+    Type {
+        #[command(flatten)]
+        content: ContentArgs,
+        #[command(flatten)]
+        profile: ProfileArgs,
+        /// Do not print progress
+        #[arg(short, long)]
+        quiet: bool,
+        /// Print the session report as JSON when done
+        #[arg(long)]
+        report: bool,
+        /// Write a per-keystroke log to this file
+        #[arg(long, value_name = "PATH")]
+        log: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = LogFormatArg::Jsonl)]
+        log_format: LogFormatArg,
+    },
+    /// Show what would be typed and how long it would take
+    Preview {
+        #[command(flatten)]
+        content: ContentArgs,
+        #[command(flatten)]
+        profile: ProfileArgs,
+        /// List the lines changed by normalization
+        #[arg(long)]
+        changes: bool,
+    },
+    /// Export the planned keystrokes
+    Plan {
+        #[command(flatten)]
+        content: ContentArgs,
+        #[command(flatten)]
+        profile: ProfileArgs,
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = PlanFormat::Json)]
+        format: PlanFormat,
+    },
+    /// Estimate session statistics over several simulated runs
+    Stats {
+        #[command(flatten)]
+        content: ContentArgs,
+        #[command(flatten)]
+        profile: ProfileArgs,
+        /// Number of simulated runs to average
+        #[arg(long, default_value_t = 10)]
+        runs: u32,
+    },
+    /// Fit a profile to a keystroke recording (JSON or JSON Lines)
+    Calibrate {
+        /// Recording, e.g. a keystroke log from `type --log`
+        #[arg(short, long)]
+        recording: PathBuf,
+        /// Text the recording was meant to produce (enables fitting the correction rate)
+        #[arg(long)]
+        reference: Option<PathBuf>,
+        /// Write the fitted config here (usable with --config) and the n-gram timings
+        /// next to it as <name>.ngrams.json; the report goes to stderr
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// Parameters not covered by the recording come from here
+        #[command(flatten)]
+        profile: ProfileArgs,
+    },
+    /// List the built-in profiles
+    Profiles,
+}
+
+#[derive(Args)]
+struct ContentArgs {
+    /// File to type ("-" reads stdin)
+    #[arg(short, long, conflicts_with = "text")]
+    file: Option<PathBuf>,
+    /// Literal text to type
+    #[arg(short, long)]
+    text: Option<String>,
+    /// Type the text on the clipboard
+    #[arg(long, conflicts_with_all = ["file", "text"])]
+    clipboard: bool,
+    /// Value for a `{{NAME}}` template variable (repeatable); turns templates
+    /// on
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_variable)]
+    variables: Vec<(String, String)>,
+}
+
+impl ContentArgs {
+    fn variables(&self) -> HashMap<String, String> {
+        self.variables.iter().cloned().collect()
+    }
+
+    /// `config` with templates turned on when `--var` values are given
+    fn configure(&self, mut config: Config) -> Config {
+        config.templates |= !self.variables.is_empty();
+        config
+    }
+}
+
+fn parse_variable(arg: &str) -> Result<(String, String), String> {
+    let (name, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", arg))?;
+    Ok((name.trim().to_string(), value.to_string()))
+}
+
+#[derive(Args)]
+struct ProfileArgs {
+    /// Built-in profile name
+    #[arg(short, long)]
+    profile: Option<String>,
+    /// JSON config file (same format as the app settings)
+    #[arg(long, conflicts_with = "profile")]
+    config: Option<PathBuf>,
+    /// Override the base WPM
+    #[arg(long)]
+    wpm: Option<u32>,
+    /// Language of the n-gram timing table (e.g. "en", "de")
+    #[arg(long)]
+    language: Option<String>,
+    /// Shape of the random spread applied to keystroke intervals
+    #[arg(long, value_enum)]
+    distribution: Option<DistributionArg>,
+    /// Override the countdown in seconds
+    #[arg(long, conflicts_with_all = ["wait_window", "at"])]
+    countdown: Option<u32>,
+    /// Instead of counting down, wait until a window with this title is focused
+    #[arg(long, value_name = "TITLE", conflicts_with = "at")]
+    wait_window: Option<String>,
+    /// Instead of counting down, start at this time (Unix epoch milliseconds)
+    #[arg(long, value_name = "UNIX_MS")]
+    at: Option<u64>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PlanFormat {
+    Json,
+    Jsonl,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormatArg {
+    Jsonl,
+    Csv,
+}
+
+impl From<LogFormatArg> for LogFormat {
+    fn from(format: LogFormatArg) -> Self {
+        match format {
+            LogFormatArg::Jsonl => LogFormat::Jsonl,
+            LogFormatArg::Csv => LogFormat::Csv,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DistributionArg {
+    Normal,
+    Lognormal,
+    Exgaussian,
+    Gamma,
+}
+
+impl From<DistributionArg> for IntervalDistribution {
+    fn from(family: DistributionArg) -> Self {
+        match family {
+            DistributionArg::Normal => IntervalDistribution::Normal,
+            DistributionArg::Lognormal => IntervalDistribution::LogNormal,
+            DistributionArg::Exgaussian => IntervalDistribution::ExGaussian { tail: 0.5 },
+            DistributionArg::Gamma => IntervalDistribution::Gamma,
+        }
+    }
+}
+
+/// Error with the exit code it maps to
+struct Failure {
+    code: u8,
+    message: String,
+}
+
+impl Failure {
+    fn new(code: u8, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<TypingError> for Failure {
+    fn from(e: TypingError) -> Self {
+        let code = match e {
+            TypingError::NoContent | TypingError::EmptyContent => EXIT_EMPTY_CONTENT,
+            TypingError::Keyboard(_) => EXIT_KEYBOARD,
+            TypingError::Trigger(_) | TypingError::Task(_) | TypingError::Template(_) => EXIT_FAILURE,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+/// Prints engine events to stderr
+#[derive(Clone)]
+struct ConsoleSink {
+    quiet: bool,
+}
+
+impl EventSink for ConsoleSink {
+    fn emit_event(&self, event: &str, payload: serde_json::Value) {
+        if self.quiet {
+            return;
+        }
+        match event {
+            "countdown-tick" => {
+                eprintln!("Starting in {}...", payload["remaining"]);
+            }
+            "trigger-waiting" => match payload["trigger"]["type"].as_str() {
+                Some("windowFocus") => {
+                    eprintln!("Waiting for {} to be focused...", payload["trigger"]["title"]);
+                }
+                Some("at") => eprintln!("Waiting for the scheduled start..."),
+                _ => {}
+            },
+            "focus-lost" => {
+                eprintln!("\rFocus left the target window, paused until it is refocused");
+            }
+            "break-started" => {
+                let secs = payload["durationMs"].as_u64().unwrap_or(0) / 1000;
+                eprintln!("\rTaking a {}s break", secs);
+            }
+            "typing-progress" => {
+                let percent = payload["percent"].as_f64().unwrap_or(0.0);
+                eprint!("\r{:5.1}%", percent);
+                let _ = std::io::stderr().flush();
+            }
+            "typing-state-changed" if payload["status"] == "done" => {
+                eprintln!("\rDone.  ");
+            }
+            _ => {}
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    pdf::run_helper_if_requested();
+    let cli = Cli::parse();
+    match run(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e.message);
+            ExitCode::from(e.code)
+        }
+    }
+}
+
+async fn run(command: Command) -> Result<(), Failure> {
+    match command {
+        Command::Type {
+            content,
+            profile,
+            quiet,
+            report,
+            log,
+            log_format,
+        } => {
+            let mut config = content.configure(resolve_config(&profile)?);
+            config.keystroke_log = log.is_some();
+            let document = read_content(&content, &config)?;
+
+            let engine = Arc::new(TypingEngine::new());
+            engine.set_config(config);
+            engine
+                .set_content(document)
+                .map_err(|e| Failure::new(EXIT_FAILURE, e))?;
+            engine.set_variables(content.variables());
+
+            let stopper = engine.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    stopper.stop();
+                }
+            });
+
+            engine.clone().run(ConsoleSink { quiet }).await?;
+
+            if let Some(path) = &log {
+                let data = stats::format_log(&engine.keystroke_log(), log_format.into())
+                    .map_err(|e| Failure::new(EXIT_FAILURE, e))?;
+                std::fs::write(path, data)
+                    .map_err(|e| Failure::new(EXIT_IO, format!("Failed to write log: {}", e)))?;
+            }
+            if report {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&engine.session_report())
+                        .map_err(|e| Failure::new(EXIT_FAILURE, e.to_string()))?
+                );
+            }
+
+            if engine.is_stopped() {
+                return Err(Failure::new(EXIT_INTERRUPTED, "Interrupted"));
+            }
+            Ok(())
+        }
+        Command::Preview {
+            content,
+            profile,
+            changes: show_changes,
+        } => {
+            let config = content.configure(resolve_config(&profile)?);
+            let (name, raw) = read_source(&content, config.max_file_mb)?;
+            let normalize::Normalized { text, report, changes } =
+                normalize::normalize(&raw, &config.normalization);
+            let document = render(
+                content::prepare_normalized(name, text, report, &config),
+                &content,
+                &config,
+            )?;
+            let summary = summarize(&document, &config, 1)?;
+
+            println!(
+                "{} ({} chars, {} lines)",
+                document.name,
+                summary.chars,
+                document.text.lines().count()
+            );
+            println!(
+                "Profile: {} WPM, {:.0}% mistakes, {}s countdown",
+                config.base_wpm,
+                config.mistake_rate * 100.0,
+                config.countdown_seconds
+            );
+            println!("Estimated duration: {}", format_duration(summary.duration_ms));
+            if report.total() > 0 {
+                println!("Normalized: {}", describe_normalization(&report));
+            }
+            if show_changes {
+                for change in &changes {
+                    println!("---");
+                    println!("{:>5} - {}", change.line, change.before);
+                    println!("{:>5} + {}", change.line, change.after);
+                }
+                if changes.len() == normalize::MAX_CHANGES {
+                    println!("(only the first {} changed lines are shown)", normalize::MAX_CHANGES);
+                }
+            }
+            println!("---");
+            println!("{}", document.text);
+            Ok(())
+        }
+        Command::Plan {
+            content,
+            profile,
+            out,
+            format,
+        } => {
+            let config = content.configure(resolve_config(&profile)?);
+            let document = render(read_content(&content, &config)?, &content, &config)?;
+            if document.text.is_empty() {
+                return Err(TypingError::EmptyContent.into());
+            }
+            let chunks = plan_content(&document, &config);
+
+            // Chunks are written as they are planned
+            let (mut writer, target): (Box<dyn Write>, String) = match &out {
+                Some(path) => {
+                    let file = std::fs::File::create(path).map_err(|e| {
+                        Failure::new(EXIT_IO, format!("Failed to write {}: {}", path.display(), e))
+                    })?;
+                    (Box::new(std::io::BufWriter::new(file)), path.display().to_string())
+                }
+                None => (Box::new(std::io::stdout().lock()), "stdout".to_string()),
+            };
+            write_plan(&mut writer, chunks, format)
+                .map_err(|e| Failure::new(EXIT_IO, format!("Failed to write {}: {}", target, e)))
+        }
+        Command::Stats {
+            content,
+            profile,
+            runs,
+        } => {
+            let config = content.configure(resolve_config(&profile)?);
+            let document = render(read_content(&content, &config)?, &content, &config)?;
+            let summary = summarize(&document, &config, runs.max(1))?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&summary)
+                    .map_err(|e| Failure::new(EXIT_FAILURE, e.to_string()))?
+            );
+            Ok(())
+        }
+        Command::Calibrate {
+            recording,
+            reference,
+            out,
+            profile,
+        } => {
+            let base = resolve_config(&profile)?;
+            let text = std::fs::read_to_string(&recording)
+                .map_err(|e| Failure::new(EXIT_IO, format!("Failed to read recording: {}", e)))?;
+            let reference = reference
+                .map(|path| {
+                    std::fs::read_to_string(&path)
+                        .map_err(|e| Failure::new(EXIT_IO, format!("Failed to read reference: {}", e)))
+                })
+                .transpose()?;
+
+            let keys = calibrate::parse_recording(&text).map_err(|e| Failure::new(EXIT_FAILURE, e))?;
+            let calibration = calibrate::calibrate(&keys, reference.as_deref(), &base)
+                .map_err(|e| Failure::new(EXIT_FAILURE, e))?;
+
+            match out {
+                Some(path) => {
+                    let write = |path: &Path, contents: String| {
+                        std::fs::write(path, contents).map_err(|e| {
+                            Failure::new(EXIT_IO, format!("Failed to write {}: {}", path.display(), e))
+                        })
+                    };
+                    // The profile points at its n-gram timings by absolute path
+                    let mut config = calibration.config.clone();
+                    if !calibration.digraphs.is_empty() || !calibration.trigraphs.is_empty() {
+                        let ngrams = path.with_extension("ngrams.json");
+                        let fits = serde_json::json!({
+                            "digraphs": calibration.digraphs,
+                            "trigraphs": calibration.trigraphs,
+                        });
+                        write(&ngrams, pretty_json(&fits)?)?;
+                        let ngrams = std::path::absolute(&ngrams).unwrap_or(ngrams);
+                        config.ngram_file = Some(ngrams.display().to_string());
+                    }
+                    write(&path, pretty_json(&config)?)?;
+                    eprintln!("{}", pretty_json(&calibration.report)?);
+                }
+                None => println!("{}", pretty_json(&calibration)?),
+            }
+            Ok(())
+        }
+        Command::Profiles => {
+            for name in Config::PROFILES {
+                println!("{}", name);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// "3 line endings, 2 quotes" for the steps that changed something
+fn describe_normalization(report: &NormalizationReport) -> String {
+    [
+        (report.line_endings, "line endings"),
+        (report.unicode, "lines re-composed"),
+        (report.quotes, "quotes"),
+        (report.dashes, "dashes"),
+        (report.spaces, "spaces"),
+        (report.tabs, "tabs"),
+        (report.trailing, "trailing whitespace"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, what)| format!("{} {}", count, what))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Read the content to type, prepared for typing with `config`
+fn read_content(args: &ContentArgs, config: &Config) -> Result<Document, Failure> {
+    let (name, text) = read_source(args, config.max_file_mb)?;
+    Ok(content::prepare(name, text, config))
+}
+
+/// Fill in the document's template variables from `--var` values
+fn render(mut document: Document, args: &ContentArgs, config: &Config) -> Result<Document, Failure> {
+    if config.templates {
+        let variables = args.variables();
+        document.text =
+            template::render(&document.text, &variables, &mut document.shortcuts, config.max_file_mb)
+                .map_err(|e| Failure::from(TypingError::Template(e)))?;
+    }
+    Ok(document)
+}
+
+/// Read the content and a display name for it, refusing files over
+/// `max_mb` megabytes (0 for no limit)
+fn read_source(args: &ContentArgs, max_mb: u32) -> Result<(String, String), Failure> {
+    let too_large = |e: String| Failure::new(EXIT_FAILURE, e);
+    if let Some(text) = &args.text {
+        formats::check_size(text.len() as u64, max_mb).map_err(too_large)?;
+        return Ok(("Command Line".to_string(), text.clone()));
+    }
+    if args.clipboard {
+        let text = formats::read_clipboard().map_err(|e| Failure::new(EXIT_FAILURE, e))?;
+        formats::check_size(text.len() as u64, max_mb).map_err(too_large)?;
+        return Ok((CLIPBOARD_NAME.to_string(), text));
+    }
+
+    let path = args
+        .file
+        .as_ref()
+        .ok_or_else(|| {
+            Failure::new(EXIT_EMPTY_CONTENT, "No content: pass --file, --text or --clipboard")
+        })?;
+
+    let (name, bytes) = if path.as_os_str() == "-" {
+        // Read one byte past the limit to tell whether it was exceeded
+        let mut bytes = Vec::new();
+        std::io::stdin()
+            .take(formats::byte_limit(max_mb).saturating_add(1))
+            .read_to_end(&mut bytes)
+            .map_err(|e| Failure::new(EXIT_IO, format!("Failed to read stdin: {}", e)))?;
+        formats::check_size(bytes.len() as u64, max_mb).map_err(too_large)?;
+        ("stdin".to_string(), bytes)
+    } else {
+        let size = std::fs::metadata(path)
+            .map_err(|e| Failure::new(EXIT_IO, format!("Failed to read file: {}", e)))?
+            .len();
+        formats::check_size(size, max_mb).map_err(too_large)?;
+        let bytes = std::fs::read(path)
+            .map_err(|e| Failure::new(EXIT_IO, format!("Failed to read file: {}", e)))?;
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+        (name, bytes)
+    };
+
+    let extracted =
+        formats::extract(&name, bytes, max_mb).map_err(|e| Failure::new(EXIT_FAILURE, e))?;
+    Ok((name, extracted.text))
+}
+
+/// Build the config from a profile or config file plus overrides
+fn resolve_config(args: &ProfileArgs) -> Result<Config, Failure> {
+    let mut config = if let Some(path) = &args.config {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| Failure::new(EXIT_IO, format!("Failed to read config: {}", e)))?;
+        serde_json::from_str(&raw)
+            .map_err(|e| Failure::new(EXIT_FAILURE, format!("Invalid config: {}", e)))?
+    } else if let Some(name) = &args.profile {
+        Config::profile(name).ok_or_else(|| {
+            Failure::new(
+                EXIT_FAILURE,
+                format!(
+                    "Unknown profile '{}' (available: {})",
+                    name,
+                    Config::PROFILES.join(", ")
+                ),
+            )
+        })?
+    } else {
+        Config::default()
+    };
+
+    if let Some(wpm) = args.wpm {
+        if wpm == 0 {
+            return Err(Failure::new(EXIT_FAILURE, "--wpm must be greater than 0"));
+        }
+        config.base_wpm = wpm;
+    }
+    if let Some(language) = &args.language {
+        config.language = language.clone();
+    }
+    if let Some(family) = args.distribution {
+        config.interval_distribution = family.into();
+    }
+    if let Some(countdown) = args.countdown {
+        config.countdown_seconds = countdown;
+    }
+    if let Some(title) = &args.wait_window {
+        config.start_trigger = StartTrigger::WindowFocus {
+            title: title.clone(),
+        };
+    }
+    if let Some(unix_ms) = args.at {
+        config.start_trigger = StartTrigger::At { unix_ms };
+    }
+    Ok(config)
+}
+
+/// Write planned chunks as one JSON array or one object per line
+fn write_plan(
+    writer: &mut dyn Write,
+    chunks: impl Iterator<Item = Chunk>,
+    format: PlanFormat,
+) -> std::io::Result<()> {
+    match format {
+        PlanFormat::Json => {
+            let mut serializer = serde_json::Serializer::pretty(&mut *writer);
+            serializer.collect_seq(chunks).map_err(std::io::Error::other)?;
+            writeln!(writer)?;
+        }
+        PlanFormat::Jsonl => {
+            for chunk in chunks {
+                serde_json::to_writer(&mut *writer, &chunk).map_err(std::io::Error::other)?;
+                writeln!(writer)?;
+            }
+        }
+    }
+    writer.flush()
+}
+
+/// Average the plan summary over `runs` simulated runs
+fn summarize(document: &Document, config: &Config, runs: u32) -> Result<PlanSummary, Failure> {
+    if document.text.is_empty() {
+        return Err(TypingError::EmptyContent.into());
+    }
+
+    let summaries: Vec<PlanSummary> = (0..runs)
+        .map(|_| PlanSummary::from_chunks(plan_content(document, config)))
+        .collect();
+    let n = summaries.len();
+    let avg = |f: fn(&PlanSummary) -> f64| summaries.iter().map(f).sum::<f64>() / n as f64;
+
+    Ok(PlanSummary {
+        chars: summaries[0].chars,
+        keystrokes: avg(|s| s.keystrokes as f64).round() as usize,
+        backspaces: avg(|s| s.backspaces as f64).round() as usize,
+        mistakes: avg(|s| s.mistakes as f64).round() as usize,
+        corrected: avg(|s| s.corrected as f64).round() as usize,
+        duration_ms: avg(|s| s.duration_ms as f64).round() as u64,
+        effective_wpm: avg(|s| s.effective_wpm),
+        pauses: PauseBreakdown {
+            punctuation_ms: avg(|s| s.pauses.punctuation_ms as f64).round() as u64,
+            paragraph_ms: avg(|s| s.pauses.paragraph_ms as f64).round() as u64,
+            thinking_ms: avg(|s| s.pauses.thinking_ms as f64).round() as u64,
+            composition_ms: avg(|s| s.pauses.composition_ms as f64).round() as u64,
+        },
+    })
+}
+
+fn pretty_json<T: Serialize>(value: &T) -> Result<String, Failure> {
+    serde_json::to_string_pretty(value).map_err(|e| Failure::new(EXIT_FAILURE, e.to_string()))
+}
+
+fn format_duration(ms: u64) -> String {
+    let secs = ms / 1000;
+    if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    }
+}
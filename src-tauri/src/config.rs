@@ -1,18 +1,72 @@
 use serde::{Deserialize, Serialize};
 
+use crate::content::formats::{SourceFormat, TextEncoding};
+use crate::content::markdown::{EditorShortcuts, MarkdownMode};
+use crate::content::normalize::{Normalization, NormalizationReport};
+use crate::typer::breaks::BreakSchedule;
+use crate::typer::code::CodeLanguage;
+use crate::typer::distribution::IntervalDistribution;
+use crate::typer::fatigue::FatigueModel;
+use crate::typer::trigger::StartTrigger;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct Config {
     pub base_wpm: u32,
     pub wpm_variance: f64,
+    /// Shape of the random spread applied to keystroke intervals and pauses
+    pub interval_distribution: IntervalDistribution,
     pub mistake_rate: f64,
     pub correction_rate: f64,
     pub punctuation_pause: u64,
     pub paragraph_pause: u64,
     pub thinking_pause_chance: f64,
     pub thinking_pause_duration: u64,
+    /// Pause before starting a sentence, scaled up for paragraphs and long or
+    /// involved units and down for clauses (0 disables)
+    pub composition_pause: u64,
     pub burst_typing: bool,
+    /// Slow down on rare words and identifiers, speed up on common words
+    pub word_difficulty: bool,
+    /// Use the code timing model for source files
+    pub code_timing: bool,
+    /// Warmup at the start and fatigue over long sessions
+    pub fatigue: FatigueModel,
+    /// Scheduled breaks during long sessions
+    pub breaks: BreakSchedule,
+    /// Average time a key is held down. 0 (the default) types each character
+    /// in one go; anything else sends separate press and release events,
+    /// which some targets (remote desktops, some X11/Wayland setups) handle
+    /// worse.
+    pub key_hold_ms: u64,
+    /// Let the next key go down before the previous one is released (with
+    /// `key_hold_ms` above 0)
+    pub rollover: bool,
+    /// Clean-up applied to content when it is loaded
+    pub normalization: Normalization,
+    /// How Markdown files are typed
+    pub markdown: MarkdownMode,
+    /// Shortcuts for formatting Markdown in the target editor
+    pub editor_shortcuts: EditorShortcuts,
+    /// Fill in `{{name}}` placeholders before typing. Off by default, since
+    /// many templating languages and docs use the same braces.
+    pub templates: bool,
+    /// Reload a file loaded with `load_file` when it changes on disk, until
+    /// other content is loaded
+    pub watch_file: bool,
+    /// Largest file that can be loaded, in megabytes (0 for no limit)
+    pub max_file_mb: u32,
+    /// Language of the n-gram timing table
+    pub language: String,
+    /// User calibration file with n-gram timings overriding the bundled table
+    pub ngram_file: Option<String>,
     pub countdown_seconds: u32,
+    /// What has to happen before typing starts
+    pub start_trigger: StartTrigger,
+    /// Pause automatically if focus moves away from the window typing started in
+    pub focus_guard: bool,
+    /// Keep a per-keystroke log of each run for export
+    pub keystroke_log: bool,
 }
 
 impl Default for Config {
@@ -20,33 +74,197 @@ impl Default for Config {
         Self {
             base_wpm: 60,
             wpm_variance: 0.3,
+            interval_distribution: IntervalDistribution::default(),
             mistake_rate: 0.03,
             correction_rate: 0.7,
             punctuation_pause: 300,
             paragraph_pause: 800,
             thinking_pause_chance: 0.02,
             thinking_pause_duration: 1500,
+            composition_pause: 500,
             burst_typing: true,
+            word_difficulty: true,
+            code_timing: true,
+            fatigue: FatigueModel::default(),
+            breaks: BreakSchedule::default(),
+            key_hold_ms: 0,
+            rollover: false,
+            normalization: Normalization::default(),
+            markdown: MarkdownMode::default(),
+            editor_shortcuts: EditorShortcuts::default(),
+            templates: false,
+            watch_file: false,
+            max_file_mb: 64,
+            language: "en".to_string(),
+            ngram_file: None,
             countdown_seconds: 3,
+            start_trigger: StartTrigger::default(),
+            focus_guard: true,
+            keystroke_log: false,
+        }
+    }
+}
+
+impl Config {
+    /// Names of the built-in profiles accepted by [`Config::profile`]
+    pub const PROFILES: &'static [&'static str] = &["normal", "slow", "fast", "sloppy"];
+
+    /// Look up a built-in profile by name (case-insensitive)
+    pub fn profile(name: &str) -> Option<Self> {
+        let base = Self::default();
+        match name.to_ascii_lowercase().as_str() {
+            "normal" | "default" => Some(base),
+            "slow" => Some(Self {
+                base_wpm: 35,
+                wpm_variance: 0.35,
+                mistake_rate: 0.04,
+                punctuation_pause: 450,
+                paragraph_pause: 1200,
+                thinking_pause_chance: 0.04,
+                thinking_pause_duration: 2000,
+                composition_pause: 800,
+                burst_typing: false,
+                ..base
+            }),
+            "fast" => Some(Self {
+                base_wpm: 95,
+                wpm_variance: 0.2,
+                mistake_rate: 0.02,
+                correction_rate: 0.9,
+                punctuation_pause: 180,
+                paragraph_pause: 500,
+                thinking_pause_chance: 0.01,
+                thinking_pause_duration: 900,
+                composition_pause: 300,
+                ..base
+            }),
+            "sloppy" => Some(Self {
+                base_wpm: 70,
+                wpm_variance: 0.45,
+                mistake_rate: 0.08,
+                correction_rate: 0.5,
+                ..base
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Engine actions that can be bound to a global shortcut
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HotkeyAction {
+    StartStop,
+    PauseResume,
+    SkipParagraph,
+    SpeedUp,
+    SpeedDown,
+    Abort,
+    TypeClipboard,
+}
+
+impl HotkeyAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::StartStop => "start/stop",
+            Self::PauseResume => "pause/resume",
+            Self::SkipParagraph => "skip paragraph",
+            Self::SpeedUp => "speed up",
+            Self::SpeedDown => "speed down",
+            Self::Abort => "abort",
+            Self::TypeClipboard => "type clipboard",
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Global shortcut bindings (e.g. "Ctrl+Alt+S"); `None` leaves an action unbound
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Hotkeys {
+    pub start_stop: Option<String>,
+    pub pause_resume: Option<String>,
+    pub skip_paragraph: Option<String>,
+    pub speed_up: Option<String>,
+    pub speed_down: Option<String>,
+    pub abort: Option<String>,
+    /// Load the clipboard text and start typing it
+    pub type_clipboard: Option<String>,
+    /// Whether the abort hotkey backspaces everything typed in the run
+    pub undo_on_abort: bool,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            start_stop: Some("Ctrl+Alt+S".to_string()),
+            pause_resume: Some("Ctrl+Alt+P".to_string()),
+            skip_paragraph: Some("Ctrl+Alt+N".to_string()),
+            speed_up: Some("Ctrl+Alt+Up".to_string()),
+            speed_down: Some("Ctrl+Alt+Down".to_string()),
+            abort: Some("Ctrl+Alt+Q".to_string()),
+            type_clipboard: Some("Ctrl+Alt+V".to_string()),
+            undo_on_abort: true,
+        }
+    }
+}
+
+impl Hotkeys {
+    /// Bound actions with their shortcut strings
+    pub fn bindings(&self) -> Vec<(HotkeyAction, &str)> {
+        [
+            (HotkeyAction::StartStop, &self.start_stop),
+            (HotkeyAction::PauseResume, &self.pause_resume),
+            (HotkeyAction::SkipParagraph, &self.skip_paragraph),
+            (HotkeyAction::SpeedUp, &self.speed_up),
+            (HotkeyAction::SpeedDown, &self.speed_down),
+            (HotkeyAction::Abort, &self.abort),
+            (HotkeyAction::TypeClipboard, &self.type_clipboard),
+        ]
+        .into_iter()
+        .filter_map(|(action, shortcut)| {
+            shortcut
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| (action, s))
+        })
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TypingStatus {
+    #[default]
     Idle,
     Ready,
     Countdown,
+    /// Waiting for the hotkey before the next queue item
+    Waiting,
     Typing,
     Paused,
+    /// Paused because the target window lost focus
+    #[serde(rename = "focusLost")]
+    FocusLost,
+    /// Taking a scheduled break
+    #[serde(rename = "onBreak")]
+    OnBreak,
     Done,
     Error,
 }
 
-impl Default for TypingStatus {
-    fn default() -> Self {
-        Self::Idle
+impl TypingStatus {
+    /// Whether a run is in progress
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            Self::Countdown
+                | Self::Waiting
+                | Self::Typing
+                | Self::Paused
+                | Self::FocusLost
+                | Self::OnBreak
+        )
     }
 }
 
@@ -55,6 +273,12 @@ pub struct TypingProgress {
     pub current: u32,
     pub total: u32,
     pub percent: f32,
+    /// Target speed in effect
+    pub wpm: u32,
+    /// Index of the queue item being typed
+    pub item: u32,
+    /// Number of queued items
+    pub items: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,8 +297,22 @@ pub struct ErrorPayload {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FileInfo {
     pub name: String,
+    /// The content, cut short for large files (see `truncated`)
     pub content: String,
+    /// Whether `content` is only the start of the content
+    pub truncated: bool,
     pub char_count: u32,
+    /// Source language the file will be typed as, if it is code
+    pub code_language: Option<CodeLanguage>,
+    /// Format the text was extracted from
+    pub format: SourceFormat,
+    /// Detected encoding, for text-based formats
+    pub encoding: Option<TextEncoding>,
+    /// What normalization changed
+    pub normalized: NormalizationReport,
+    /// Template variables that need a value before typing
+    pub variables: Vec<String>,
 }
@@ -0,0 +1,90 @@
+use serde::Serialize;
+
+/// How content changed between two versions, by lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSummary {
+    /// First changed line (1-based), `None` when nothing changed
+    pub first_line: Option<usize>,
+    /// Lines of the old version between the unchanged start and end
+    pub lines_removed: usize,
+    /// Lines of the new version replacing them
+    pub lines_added: usize,
+    pub chars_before: usize,
+    pub chars_after: usize,
+}
+
+/// Compare `before` with `after`, trimming the lines they share at the start
+/// and the end
+pub fn summarize(before: &str, after: &str) -> DiffSummary {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let lines_removed = old.len() - prefix - suffix;
+    let lines_added = new.len() - prefix - suffix;
+    // A change in the trailing newline alone shows up in the characters only
+    let changed = lines_removed > 0 || lines_added > 0 || before != after;
+
+    DiffSummary {
+        first_line: changed.then_some(prefix + 1),
+        lines_removed,
+        lines_added,
+        chars_before: before.chars().count(),
+        chars_after: after.chars().count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_text_has_no_first_line() {
+        let summary = summarize("a\nb\n", "a\nb\n");
+        assert_eq!(summary.first_line, None);
+        assert_eq!((summary.lines_removed, summary.lines_added), (0, 0));
+    }
+
+    #[test]
+    fn changed_lines_between_shared_start_and_end() {
+        let summary = summarize("a\nb\nc\nd", "a\nx\ny\nd");
+        assert_eq!(summary.first_line, Some(2));
+        assert_eq!((summary.lines_removed, summary.lines_added), (2, 2));
+    }
+
+    #[test]
+    fn appended_lines() {
+        let summary = summarize("a\nb", "a\nb\nc\nd");
+        assert_eq!(summary.first_line, Some(3));
+        assert_eq!((summary.lines_removed, summary.lines_added), (0, 2));
+    }
+
+    #[test]
+    fn repeated_lines_are_not_counted_twice() {
+        // The shared start and end would overlap without the trim order
+        let summary = summarize("a\na", "a\na\na");
+        assert_eq!((summary.lines_removed, summary.lines_added), (0, 1));
+    }
+
+    #[test]
+    fn trailing_newline_alone_is_a_change() {
+        let summary = summarize("a\nb", "a\nb\n");
+        assert_eq!(summary.first_line, Some(3));
+        assert_eq!((summary.lines_removed, summary.lines_added), (0, 0));
+        assert_eq!((summary.chars_before, summary.chars_after), (3, 4));
+    }
+
+    #[test]
+    fn characters_not_bytes() {
+        let summary = summarize("grüß", "grüße");
+        assert_eq!((summary.chars_before, summary.chars_after), (4, 5));
+    }
+}
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+
+use super::{html, office, pdf, rtf};
+
+/// Kind of file content was extracted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SourceFormat {
+    #[default]
+    Text,
+    Html,
+    Rtf,
+    Docx,
+    Odt,
+    Pdf,
+}
+
+/// Character encoding of a text file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1, with the Windows-1252 characters in 0x80–0x9F
+    Latin1,
+}
+
+/// Text extracted from a file
+#[derive(Debug, Clone)]
+pub struct Extracted {
+    pub text: String,
+    pub format: SourceFormat,
+    /// Encoding the text was decoded from, for text-based formats
+    pub encoding: Option<TextEncoding>,
+}
+
+const MB: u64 = 1024 * 1024;
+
+/// Read `path` and extract its text, refusing files over `max_mb`
+/// megabytes (0 for no limit)
+pub fn read_file(path: &Path, max_mb: u32) -> Result<Extracted, String> {
+    let failed = |e: std::io::Error| format!("Failed to read file: {}", e);
+    let file = std::fs::File::open(path).map_err(failed)?;
+    let size = file.metadata().map_err(failed)?.len();
+    check_size(size, max_mb)?;
+
+    // Never read past the limit, even if the file grew in the meantime
+    let mut bytes = Vec::with_capacity(size as usize);
+    file.take(byte_limit(max_mb).saturating_add(1))
+        .read_to_end(&mut bytes)
+        .map_err(failed)?;
+    check_size(bytes.len() as u64, max_mb)?;
+
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    extract(name, bytes, max_mb)
+}
+
+/// Limit of `max_mb` megabytes in bytes (0 for no limit)
+pub fn byte_limit(max_mb: u32) -> u64 {
+    if max_mb > 0 {
+        max_mb as u64 * MB
+    } else {
+        u64::MAX
+    }
+}
+
+/// Error for a document that unpacks to more than `max_mb` megabytes
+pub(super) fn unpacks_too_large(max_mb: u32) -> String {
+    format!("The document unpacks to more than the {} MB limit (maxFileMb)", max_mb)
+}
+
+/// Fail if `size` bytes of content is over the limit of `max_mb` megabytes
+/// (0 for no limit)
+pub fn check_size(size: u64, max_mb: u32) -> Result<(), String> {
+    if max_mb > 0 && size > max_mb as u64 * MB {
+        return Err(format!(
+            "Content is {:.1} MB, over the {} MB limit (maxFileMb)",
+            size as f64 / MB as f64,
+            max_mb
+        ));
+    }
+    Ok(())
+}
+
+/// Current text on the system clipboard
+pub fn read_clipboard() -> Result<String, String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Failed to open the clipboard: {}", e))?;
+    match clipboard.get_text() {
+        Ok(text) if !text.is_empty() => Ok(text),
+        Ok(_) | Err(arboard::Error::ContentNotAvailable) => Err("The clipboard has no text".to_string()),
+        Err(e) => Err(format!("Failed to read the clipboard: {}", e)),
+    }
+}
+
+/// Extract the text of a file called `name`. The format is detected from the
+/// content, falling back to the extension for HTML. UTF-8 text keeps the
+/// buffer it was read into. Compressed formats may not unpack to more than
+/// `max_mb` megabytes (0 for no limit).
+pub fn extract(name: &str, bytes: Vec<u8>, max_mb: u32) -> Result<Extracted, String> {
+    let ext = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    let binary = |text: String, format: SourceFormat| Extracted {
+        text: tidy(&text),
+        format,
+        encoding: None,
+    };
+    if bytes.starts_with(b"PK\x03\x04") {
+        return match office::detect(&bytes)? {
+            SourceFormat::Odt => Ok(binary(office::odt_text(&bytes, max_mb)?, SourceFormat::Odt)),
+            _ => Ok(binary(office::docx_text(&bytes, max_mb)?, SourceFormat::Docx)),
+        };
+    }
+    if bytes.starts_with(b"%PDF") {
+        let text = pdf::text(&bytes, max_mb)?;
+        return Ok(binary(join_wrapped_lines(&text), SourceFormat::Pdf));
+    }
+
+    let (text, encoding) = decode(bytes);
+    let format = if text.trim_start().starts_with("{\\rtf") {
+        SourceFormat::Rtf
+    } else if matches!(ext.as_str(), "html" | "htm" | "xhtml") || html::looks_like_html(&text) {
+        SourceFormat::Html
+    } else {
+        SourceFormat::Text
+    };
+    let text = match format {
+        SourceFormat::Rtf => tidy(&rtf::to_text(&text)),
+        SourceFormat::Html => tidy(&html::to_text(&text)),
+        _ => text,
+    };
+    Ok(Extracted {
+        text,
+        format,
+        encoding: Some(encoding),
+    })
+}
+
+/// Decode text, detecting the encoding from a byte order mark, the pattern of
+/// zero bytes typical of UTF-16, or whether it is valid UTF-8
+pub fn decode(mut bytes: Vec<u8>) -> (String, TextEncoding) {
+    if bytes.starts_with(b"\xEF\xBB\xBF") {
+        bytes.drain(..3);
+        let text = String::from_utf8(bytes)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
+        return (text, TextEncoding::Utf8);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return (utf16(rest, u16::from_le_bytes), TextEncoding::Utf16Le);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return (utf16(rest, u16::from_be_bytes), TextEncoding::Utf16Be);
+    }
+
+    // Mostly-ASCII UTF-16 has a zero in every other byte
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    if sample.len() >= 4 {
+        let zeros = |offset: usize| sample.iter().skip(offset).step_by(2).filter(|&&b| b == 0).count();
+        let half = sample.len() / 2;
+        let (even, odd) = (zeros(0), zeros(1));
+        if odd * 10 >= half * 7 && even * 10 < half {
+            return (utf16(&bytes, u16::from_le_bytes), TextEncoding::Utf16Le);
+        }
+        if even * 10 >= half * 7 && odd * 10 < half {
+            return (utf16(&bytes, u16::from_be_bytes), TextEncoding::Utf16Be);
+        }
+    }
+
+    match String::from_utf8(bytes) {
+        Ok(text) => (text, TextEncoding::Utf8),
+        Err(e) => (e.as_bytes().iter().map(|&b| latin1(b)).collect(), TextEncoding::Latin1),
+    }
+}
+
+fn utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// A Latin-1 byte, reading 0x80–0x9F as Windows-1252 like most software does
+pub(super) fn latin1(byte: u8) -> char {
+    const WINDOWS_1252: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+        '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9F => WINDOWS_1252[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+/// Clean up extracted text: no trailing spaces, at most one blank line in a
+/// row, nothing blank at either end
+fn tidy(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank = 0;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank > 0 { "\n\n" } else { "\n" });
+        }
+        out.push_str(line);
+        blank = 0;
+    }
+    out
+}
+
+/// Undo the hard line wrapping of PDF text: lines within a paragraph are
+/// joined, rejoining words hyphenated across lines
+fn join_wrapped_lines(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_paragraph = false;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            if in_paragraph {
+                out.push_str("\n\n");
+            }
+            in_paragraph = false;
+            continue;
+        }
+        if in_paragraph {
+            let hyphenated = out.ends_with('-') && out[..out.len() - 1].ends_with(char::is_alphabetic);
+            if hyphenated && line.starts_with(char::is_lowercase) {
+                out.pop();
+            } else {
+                out.push(' ');
+            }
+        }
+        out.push_str(line);
+        in_paragraph = true;
+    }
+    out
+}
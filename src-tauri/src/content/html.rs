@@ -0,0 +1,231 @@
+/// Whether `text` looks like an HTML document
+pub fn looks_like_html(text: &str) -> bool {
+    let start: String = text.trim_start().chars().take(64).collect::<String>().to_ascii_lowercase();
+    start.starts_with("<!doctype html") || start.starts_with("<html")
+}
+
+/// Elements whose content isn't text
+const SKIPPED: &[&str] = &["head", "script", "style", "noscript", "template", "svg"];
+
+/// Elements that start a new paragraph
+const BLOCKS: &[&str] = &[
+    "p", "div", "section", "article", "header", "footer", "aside", "nav", "main", "blockquote", "pre",
+    "h1", "h2", "h3", "h4", "h5", "h6", "ul", "ol", "dl", "table", "figure", "hr", "address", "form",
+];
+
+/// Elements that start a new line
+const LINES: &[&str] = &["br", "li", "dt", "dd", "tr", "figcaption"];
+
+/// Text content of an HTML document, with a blank line between paragraphs
+pub fn to_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+    let mut skipping: Option<String> = None;
+    let mut pre = 0usize;
+
+    while !rest.is_empty() {
+        // Skipped content (scripts in particular) may contain `<` and `>`,
+        // so look straight for the closing tag
+        if let Some(skipped) = skipping.take() {
+            let end = find_closing_tag(rest, &skipped).and_then(|at| rest[at..].find('>').map(|close| at + close + 1));
+            rest = end.map_or("", |end| &rest[end..]);
+            continue;
+        }
+        let Some(open) = rest.find('<') else {
+            push_text(&mut out, rest, pre > 0);
+            break;
+        };
+        push_text(&mut out, &rest[..open], pre > 0);
+        rest = &rest[open..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(close) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..close];
+        rest = &rest[close + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if !closing && SKIPPED.contains(&name.as_str()) && !tag.ends_with('/') {
+            skipping = Some(name);
+            continue;
+        }
+
+        if name == "pre" {
+            pre = if closing { pre.saturating_sub(1) } else { pre + 1 };
+        }
+        if BLOCKS.contains(&name.as_str()) {
+            break_line(&mut out, true);
+        } else if name == "br" || (!closing && LINES.contains(&name.as_str())) {
+            break_line(&mut out, false);
+        } else if !closing && matches!(name.as_str(), "td" | "th") && !out.ends_with(['\n', '\t']) && !out.is_empty() {
+            out.push('\t');
+        }
+    }
+    out
+}
+
+/// Start of the first `</name` in `html`, ignoring case
+fn find_closing_tag(html: &str, name: &str) -> Option<usize> {
+    let bytes = html.as_bytes();
+    (0..bytes.len()).find(|&i| {
+        bytes[i..].starts_with(b"</")
+            && bytes
+                .get(i + 2..i + 2 + name.len())
+                .is_some_and(|tag| tag.eq_ignore_ascii_case(name.as_bytes()))
+            && !bytes.get(i + 2 + name.len()).is_some_and(u8::is_ascii_alphanumeric)
+    })
+}
+
+/// Append text, collapsing whitespace unless it is preformatted
+fn push_text(out: &mut String, text: &str, preformatted: bool) {
+    let text = decode_entities(text);
+    if preformatted {
+        out.push_str(&text);
+        return;
+    }
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !out.is_empty() && !out.ends_with([' ', '\n', '\t']) {
+                out.push(' ');
+            }
+        } else {
+            out.push(if c == '\u{a0}' { ' ' } else { c });
+        }
+    }
+}
+
+/// End the current line, leaving a blank line after it for a `paragraph`
+fn break_line(out: &mut String, paragraph: bool) {
+    while out.ends_with(' ') {
+        out.pop();
+    }
+    if out.is_empty() {
+        return;
+    }
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    if paragraph && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+/// Replace character references (`&amp;`, `&#8217;`, `&#x2014;`)
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "bull" => '•',
+        "middot" => '·',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "deg" => '°',
+        "euro" => '€',
+        "shy" => '\u{ad}',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn documents_are_recognized_by_their_start() {
+        assert!(looks_like_html("\n  <!DOCTYPE html><html>"));
+        assert!(looks_like_html("<HTML lang=\"en\">"));
+        assert!(!looks_like_html("<p>fragment</p>"));
+    }
+
+    #[test]
+    fn blocks_and_lines() {
+        let html = "<html><head><title>T</title></head><body><h1>Title</h1>\n<p>One   two\nthree</p><p>a<br>b</p><ul><li>x</li><li>y</li></ul></body></html>";
+        assert_eq!(to_text(html), "Title\n\nOne two three\n\na\nb\n\nx\ny\n\n");
+    }
+
+    #[test]
+    fn scripts_styles_and_comments_are_dropped() {
+        let html = "<p>a<script>if (x < 1 && y > 2) {}</script><STYLE>p{}</Style ><!-- <p>gone</p> -->b<svg/>c</p>";
+        assert_eq!(to_text(html), "abc\n\n");
+        assert_eq!(to_text("<p>a</p><script>never closed"), "a\n\n");
+    }
+
+    #[test]
+    fn entities_are_decoded() {
+        assert_eq!(
+            to_text("<p>&lt;a&gt; &amp; &#8217;&#x2014;&nbsp;&nbsp;x &bogus; & y</p>"),
+            "<a> & ’—  x &bogus; & y\n\n"
+        );
+    }
+
+    #[test]
+    fn preformatted_text_keeps_its_whitespace() {
+        assert_eq!(to_text("<pre>fn  x() {\n    y\n}</pre><p>a  b</p>"), "fn  x() {\n    y\n}\n\na b\n\n");
+    }
+
+    #[test]
+    fn table_cells_are_tab_separated() {
+        let html = "<table><tr><th>A</th><th>B</th></tr><tr><td>1</td><td>2</td></tr></table>";
+        assert_eq!(to_text(html), "A\tB\n1\t2\n\n");
+    }
+}
@@ -0,0 +1,475 @@
+use serde::{Deserialize, Serialize};
+
+use super::Shortcut;
+use crate::typer::keyboard::KeyCombo;
+
+/// What to do with Markdown formatting in .md files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MarkdownMode {
+    /// Type the Markdown source as-is
+    #[default]
+    Verbatim,
+    /// Drop the formatting and type plain text
+    Strip,
+    /// Type plain text and apply formatting with editor shortcuts
+    Shortcuts,
+}
+
+/// Editor shortcuts used in [`MarkdownMode::Shortcuts`] (e.g. "Ctrl+B");
+/// `None` leaves that formatting out. Bold and italic are pressed again to
+/// turn them off. A heading shortcut is pressed once at the start of the
+/// heading line: editors have no shortcut that undoes a paragraph style, and
+/// go back to body text on the Enter that ends a heading (Word, Google Docs,
+/// LibreOffice).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EditorShortcuts {
+    pub bold: Option<String>,
+    pub italic: Option<String>,
+    pub heading1: Option<String>,
+    pub heading2: Option<String>,
+    /// Also used for deeper headings
+    pub heading3: Option<String>,
+}
+
+impl Default for EditorShortcuts {
+    fn default() -> Self {
+        Self {
+            bold: Some("Ctrl+B".to_string()),
+            italic: Some("Ctrl+I".to_string()),
+            heading1: Some("Ctrl+Alt+1".to_string()),
+            heading2: Some("Ctrl+Alt+2".to_string()),
+            heading3: Some("Ctrl+Alt+3".to_string()),
+        }
+    }
+}
+
+/// Parsed [`EditorShortcuts`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EditorKeys {
+    pub bold: Option<KeyCombo>,
+    pub italic: Option<KeyCombo>,
+    pub headings: [Option<KeyCombo>; 3],
+}
+
+impl EditorShortcuts {
+    pub fn parse(&self) -> Result<EditorKeys, String> {
+        let parse = |combo: &Option<String>| -> Result<Option<KeyCombo>, String> {
+            match combo.as_deref().map(str::trim) {
+                Some(combo) if !combo.is_empty() => KeyCombo::parse(combo).map(Some),
+                _ => Ok(None),
+            }
+        };
+        Ok(EditorKeys {
+            bold: parse(&self.bold)?,
+            italic: parse(&self.italic)?,
+            headings: [parse(&self.heading1)?, parse(&self.heading2)?, parse(&self.heading3)?],
+        })
+    }
+}
+
+/// Convert Markdown to the text to type, plus the shortcuts to press along
+/// the way when `mode` is [`MarkdownMode::Shortcuts`]. Invalid shortcuts
+/// are left out. With `templates`, a `\{{` escape keeps its backslash for
+/// the template step to remove.
+pub fn convert(
+    markdown: &str,
+    mode: MarkdownMode,
+    shortcuts: &EditorShortcuts,
+    templates: bool,
+) -> (String, Vec<Shortcut>) {
+    let keys = match mode {
+        MarkdownMode::Shortcuts => shortcuts.parse().unwrap_or_default(),
+        _ => EditorKeys::default(),
+    };
+    let mut out = Output {
+        shortcuts_mode: mode == MarkdownMode::Shortcuts,
+        template_escapes: templates,
+        keys,
+        ..Default::default()
+    };
+    let mut fence: Option<String> = None;
+    // Whether the previous line was a list item
+    let mut in_list = false;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some(marker) = &fence {
+            if trimmed.starts_with(marker.as_str()) {
+                fence = None;
+            } else {
+                out.push_str(line);
+                out.push('\n');
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(trimmed[..3].to_string());
+            in_list = false;
+            continue;
+        }
+
+        let mut line = trimmed;
+        while let Some(rest) = line.strip_prefix('>') {
+            line = rest.strip_prefix(' ').unwrap_or(rest);
+        }
+
+        if is_rule(line) {
+            in_list = false;
+            continue;
+        }
+
+        if let Some((level, text)) = heading(line) {
+            in_list = false;
+            if let Some(keys) = out.keys.headings[level.min(3) - 1] {
+                out.shortcut(keys);
+            }
+            out.inline(text.trim_end_matches(['#', ' ']));
+            out.push('\n');
+            continue;
+        }
+
+        if let Some((marker, text)) = list_item(line) {
+            if out.shortcuts_mode {
+                // The first marker makes the editor start a list; after that
+                // Enter continues it
+                if !in_list {
+                    out.push_str(&marker);
+                }
+            } else if marker.starts_with(|c: char| c.is_ascii_digit()) {
+                out.push_str(&marker);
+            }
+            in_list = true;
+            out.inline(text);
+            out.push('\n');
+            continue;
+        }
+
+        // A blank line ends a list in the editor too. Markdown treats
+        // several blank lines (or ones left by removed rules and fences) as
+        // one.
+        in_list = false;
+        if line.trim().is_empty() {
+            if !out.text.is_empty() && !out.text.ends_with("\n\n") {
+                out.push('\n');
+            }
+            continue;
+        }
+        out.inline(line.trim_end());
+        out.push('\n');
+    }
+
+    if !markdown.ends_with('\n') && out.text.pop().is_some() {
+        out.len -= 1;
+    }
+    out.close_all();
+    (out.text, out.shortcuts)
+}
+
+#[derive(Default)]
+struct Output {
+    text: String,
+    /// Characters in `text`
+    len: usize,
+    shortcuts: Vec<Shortcut>,
+    shortcuts_mode: bool,
+    /// Leave `\{{` for the template step
+    template_escapes: bool,
+    keys: EditorKeys,
+    bold: bool,
+    italic: bool,
+}
+
+impl Output {
+    fn push(&mut self, c: char) {
+        self.text.push(c);
+        self.len += 1;
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.text.push_str(s);
+        self.len += s.chars().count();
+    }
+
+    fn shortcut(&mut self, keys: KeyCombo) {
+        self.shortcuts.push(Shortcut { index: self.len, keys });
+    }
+
+    fn toggle_bold(&mut self) {
+        self.bold = !self.bold;
+        if let Some(keys) = self.keys.bold {
+            self.shortcut(keys);
+        }
+    }
+
+    fn toggle_italic(&mut self) {
+        self.italic = !self.italic;
+        if let Some(keys) = self.keys.italic {
+            self.shortcut(keys);
+        }
+    }
+
+    /// Turn off formatting left open
+    fn close_all(&mut self) {
+        if self.italic {
+            self.toggle_italic();
+        }
+        if self.bold {
+            self.toggle_bold();
+        }
+    }
+
+    /// Type a line of inline Markdown. Emphasis doesn't carry over to the
+    /// next line.
+    fn inline(&mut self, line: &str) {
+        self.spans(line);
+        self.close_all();
+    }
+
+    fn spans(&mut self, line: &str) {
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            match c {
+                '\\' if self.template_escapes && chars[i + 1..].starts_with(&['{', '{']) => {
+                    self.push_str("\\{{");
+                    i += 3;
+                }
+                '\\' if chars.get(i + 1).is_some_and(|n| n.is_ascii_punctuation()) => {
+                    self.push(chars[i + 1]);
+                    i += 2;
+                }
+                '`' => {
+                    let ticks = chars[i..].iter().take_while(|&&t| t == '`').count();
+                    let close = find_run(&chars, i + ticks, '`', ticks);
+                    match close {
+                        Some(end) => {
+                            let code: String = chars[i + ticks..end].iter().collect();
+                            self.push_str(code.trim());
+                            i = end + ticks;
+                        }
+                        None => {
+                            (0..ticks).for_each(|_| self.push('`'));
+                            i += ticks;
+                        }
+                    }
+                }
+                '!' | '[' => match link(&chars, i) {
+                    Some((text, end)) => {
+                        self.spans(&text);
+                        i = end;
+                    }
+                    None => {
+                        self.push(c);
+                        i += 1;
+                    }
+                },
+                '*' | '_' => {
+                    let run = chars[i..].iter().take_while(|&&d| d == c).count();
+                    let prev = i.checked_sub(1).map(|p| chars[p]);
+                    let next = chars.get(i + run).copied();
+                    // Delimiters the run could not use are literal, so they
+                    // are not read again as a closer right after the opener
+                    let consumed = self.emphasis(&chars, i, c, run, prev, next);
+                    (consumed..run).for_each(|_| self.push(c));
+                    i += run;
+                }
+                _ => {
+                    self.push(c);
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// Handle a run of `run` emphasis delimiters at `chars[i]`, returning how
+    /// many were used (0 if they are literal)
+    fn emphasis(&mut self, chars: &[char], i: usize, delim: char, run: usize, prev: Option<char>, next: Option<char>) -> usize {
+        let word_char = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+        let space = |c: Option<char>| c.is_none_or(char::is_whitespace);
+        // Underscores inside words (snake_case) are literal
+        if delim == '_' && word_char(prev) && word_char(next) {
+            return 0;
+        }
+        let closing = !space(prev);
+        let opening = !space(next);
+
+        let mut used = 0;
+        let bold = if self.bold { closing } else { opening && find_run(chars, i + run, delim, 2).is_some() };
+        if run >= 2 && bold {
+            self.toggle_bold();
+            used = 2;
+        }
+        let italic = if self.italic { closing } else { opening && find_run(chars, i + run, delim, 1).is_some() };
+        if run > used && italic {
+            self.toggle_italic();
+            used += 1;
+        }
+        used
+    }
+}
+
+/// Start of the next run of at least `len` `delim`s from `from`
+fn find_run(chars: &[char], from: usize, delim: char, len: usize) -> Option<usize> {
+    (from..chars.len()).find(|&j| chars[j..].iter().take_while(|&&d| d == delim).count() >= len)
+}
+
+/// Text of a `[text](url)` link or alt text of a `![alt](url)` image at
+/// `chars[i]`, and the index after it
+fn link(chars: &[char], i: usize) -> Option<(String, usize)> {
+    let open = if chars[i] == '!' { i + 1 } else { i };
+    if chars.get(open) != Some(&'[') {
+        return None;
+    }
+    let close = (open + 1..chars.len()).find(|&j| chars[j] == ']')?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let end = (close + 2..chars.len()).find(|&j| chars[j] == ')')?;
+    Some((chars[open + 1..close].iter().collect(), end + 1))
+}
+
+/// Level and text of an ATX heading ("## Title")
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if rest.is_empty() {
+        return Some((level, rest));
+    }
+    rest.strip_prefix(' ').map(|text| (level, text.trim_start()))
+}
+
+/// Marker ("- " or "1. ") and text of a list item
+fn list_item(line: &str) -> Option<(String, &str)> {
+    if let Some(text) = ["- ", "* ", "+ "].iter().find_map(|m| line.strip_prefix(m)) {
+        return Some(("- ".to_string(), text.trim_start()));
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    let rest = &line[digits..];
+    let text = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") "))?;
+    Some((format!("{}. ", &line[..digits]), text.trim_start()))
+}
+
+/// Whether `line` is a thematic break ("---", "* * *")
+fn is_rule(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && matches!(marks[0], '-' | '*' | '_') && marks.iter().all(|&c| c == marks[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(markdown: &str) -> String {
+        convert(markdown, MarkdownMode::Strip, &EditorShortcuts::default(), false).0
+    }
+
+    fn with_shortcuts(markdown: &str) -> (String, Vec<(usize, String)>) {
+        let (text, shortcuts) = convert(markdown, MarkdownMode::Shortcuts, &EditorShortcuts::default(), false);
+        let shortcuts = shortcuts.iter().map(|s| (s.index, s.keys.to_string())).collect();
+        (text, shortcuts)
+    }
+
+    #[test]
+    fn strip_removes_formatting() {
+        let markdown = "# Title #\n\nSome **bold**, *italic* and `code`.\n\n---\n\nA [link](https://x) and ![alt](y.png).\n";
+        assert_eq!(strip(markdown), "Title\n\nSome bold, italic and code.\n\nA link and alt.\n");
+    }
+
+    #[test]
+    fn blank_lines_collapse_and_the_last_newline_follows_the_source() {
+        assert_eq!(strip("a\n\n\n\nb"), "a\n\nb");
+        assert_eq!(strip("a\n"), "a\n");
+    }
+
+    #[test]
+    fn fenced_code_is_kept_verbatim() {
+        assert_eq!(strip("```rust\nlet _x = **y**;\n```\n"), "let _x = **y**;\n");
+    }
+
+    #[test]
+    fn lists_keep_numbers_but_not_bullets() {
+        assert_eq!(strip("- one\n* two\n\n1. first\n2) second\n"), "one\ntwo\n\n1. first\n2. second\n");
+    }
+
+    #[test]
+    fn quotes_lose_their_markers() {
+        assert_eq!(strip("> > nested\n"), "nested\n");
+    }
+
+    #[test]
+    fn underscores_inside_words_are_literal() {
+        assert_eq!(strip("call max_file_mb or _this_\n"), "call max_file_mb or this\n");
+        assert_eq!(strip("2 * 3 * 4\n"), "2 * 3 * 4\n");
+    }
+
+    #[test]
+    fn backslash_escapes() {
+        assert_eq!(strip("\\*not italic\\*\n"), "*not italic*\n");
+        assert_eq!(strip("\\{{name}}\n"), "{{name}}\n");
+    }
+
+    #[test]
+    fn template_escapes_survive_for_the_template_step() {
+        let (text, _) = convert("\\{{name}} \\*x\\*\n", MarkdownMode::Strip, &EditorShortcuts::default(), true);
+        assert_eq!(text, "\\{{name}} *x*\n");
+    }
+
+    #[test]
+    fn emphasis_is_toggled_on_and_off() {
+        let (text, shortcuts) = with_shortcuts("a **b** *c*\n");
+        assert_eq!(text, "a b c\n");
+        assert_eq!(
+            shortcuts,
+            [(2, "Ctrl+B".into()), (3, "Ctrl+B".into()), (4, "Ctrl+I".into()), (5, "Ctrl+I".into())]
+        );
+    }
+
+    #[test]
+    fn unmatched_delimiters_are_typed() {
+        let (text, shortcuts) = with_shortcuts("**open and *closed*\n");
+        assert_eq!(text, "*open and *closed\n");
+        assert_eq!(shortcuts, [(0, "Ctrl+I".into()), (17, "Ctrl+I".into())]);
+    }
+
+    #[test]
+    fn headings_press_their_shortcut_once() {
+        let (text, shortcuts) = with_shortcuts("# One\ntext\n#### Four\n");
+        assert_eq!(text, "One\ntext\nFour\n");
+        assert_eq!(shortcuts, [(0, "Ctrl+Alt+1".into()), (9, "Ctrl+Alt+3".into())]);
+    }
+
+    #[test]
+    fn only_the_first_list_marker_is_typed_with_shortcuts() {
+        let (text, _) = with_shortcuts("- a\n- b\n\n- c\n");
+        assert_eq!(text, "- a\nb\n\n- c\n");
+    }
+
+    #[test]
+    fn invalid_or_empty_shortcuts_are_left_out() {
+        let shortcuts = EditorShortcuts {
+            bold: Some("Ctrl+Nope+B".into()),
+            italic: Some(" ".into()),
+            ..EditorShortcuts::default()
+        };
+        assert!(shortcuts.parse().is_err());
+        let (text, pressed) = convert("**a** *b*\n", MarkdownMode::Shortcuts, &shortcuts, false);
+        assert_eq!(text, "a b\n");
+        assert!(pressed.is_empty());
+    }
+
+    #[test]
+    fn verbatim_mode_types_no_shortcuts() {
+        let (_, pressed) = convert("**a**\n", MarkdownMode::Verbatim, &EditorShortcuts::default(), false);
+        assert!(pressed.is_empty());
+    }
+}
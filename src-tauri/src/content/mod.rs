@@ -0,0 +1,105 @@
+//! Turning loaded files into what actually gets typed.
+
+pub mod diff;
+pub mod formats;
+mod html;
+pub mod markdown;
+pub mod normalize;
+mod office;
+pub mod pdf;
+mod rtf;
+pub mod template;
+
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::typer::code::CodeLanguage;
+use crate::typer::keyboard::KeyCombo;
+use markdown::MarkdownMode;
+use normalize::NormalizationReport;
+
+/// A shortcut pressed before typing the character at `index`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Shortcut {
+    pub index: usize,
+    pub keys: KeyCombo,
+}
+
+/// Content ready to be typed
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    pub name: String,
+    /// Shared with the queue and the planner rather than copied
+    pub text: Arc<str>,
+    /// Source language when the content is code
+    pub code_language: Option<CodeLanguage>,
+    /// Editor shortcuts, in order of `index`
+    pub shortcuts: Vec<Shortcut>,
+    /// What normalization changed
+    pub normalized: NormalizationReport,
+    /// Template variables that need a value before typing
+    pub variables: Vec<String>,
+}
+
+impl Document {
+    /// Content typed as-is
+    pub fn plain(name: String, text: String) -> Self {
+        Self {
+            code_language: CodeLanguage::from_path(&name),
+            name,
+            text: text.into(),
+            shortcuts: Vec::new(),
+            normalized: NormalizationReport::default(),
+            variables: Vec::new(),
+        }
+    }
+}
+
+/// Whether `name` is a Markdown file
+pub fn is_markdown(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "md" | "markdown" | "mdown" | "mkd"))
+}
+
+/// Prepare loaded content for typing according to `config`: normalize it,
+/// handle Markdown, then list the template variables it needs
+pub fn prepare(name: String, text: String, config: &Config) -> Document {
+    // Content typed verbatim is not copied
+    let (text, report) = if config.normalization.is_noop() {
+        (text, NormalizationReport::default())
+    } else {
+        let normalized = normalize::normalize(&text, &config.normalization);
+        (normalized.text, normalized.report)
+    };
+    prepare_normalized(name, text, report, config)
+}
+
+/// The rest of [`prepare`] for text that `report` says has already been
+/// normalized
+pub fn prepare_normalized(name: String, text: String, report: NormalizationReport, config: &Config) -> Document {
+    let mut document = if config.markdown == MarkdownMode::Verbatim || !is_markdown(&name) {
+        Document {
+            normalized: report,
+            ..Document::plain(name, text)
+        }
+    } else {
+        let (text, shortcuts) =
+            markdown::convert(&text, config.markdown, &config.editor_shortcuts, config.templates);
+        Document {
+            name,
+            text: text.into(),
+            code_language: None,
+            shortcuts,
+            normalized: report,
+            variables: Vec::new(),
+        }
+    };
+    if config.templates {
+        document.variables = template::variables(&document.text);
+    }
+    document
+}
@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+/// Line endings to type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineEndings {
+    /// Leave each line's ending as it is
+    #[default]
+    Keep,
+    /// `\n` (a lone `\r` or `\r\n` would be typed as two Enters)
+    Lf,
+    Crlf,
+}
+
+/// Unicode normalization form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnicodeForm {
+    #[default]
+    None,
+    /// Compose accents with their letters
+    Nfc,
+    /// Also replace compatibility characters (ligatures, full-width
+    /// letters, "…") with plain equivalents
+    Nfkc,
+}
+
+/// Clean-up applied to content before it is typed. Every step is off by
+/// default, so content is typed verbatim unless the user opts in.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Normalization {
+    pub line_endings: LineEndings,
+    pub unicode: UnicodeForm,
+    /// Replace curly quotes and primes with ' and "
+    pub fold_quotes: bool,
+    /// Replace en dashes, minus signs and the like with -, em dashes with --
+    pub fold_dashes: bool,
+    /// Replace non-breaking and other special spaces with plain ones, and
+    /// drop zero-width characters
+    pub fold_spaces: bool,
+    /// Replace tabs with spaces up to the next multiple of this many columns
+    /// (0 keeps tabs)
+    pub expand_tabs: u32,
+    /// Remove whitespace at the end of lines
+    pub trim_trailing: bool,
+}
+
+impl Normalization {
+    /// Whether every step is off
+    pub fn is_noop(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How much each step changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizationReport {
+    /// Line endings converted
+    pub line_endings: usize,
+    /// Lines changed by Unicode normalization
+    pub unicode: usize,
+    pub quotes: usize,
+    pub dashes: usize,
+    pub spaces: usize,
+    pub tabs: usize,
+    /// Whitespace characters trimmed from line ends
+    pub trailing: usize,
+}
+
+impl NormalizationReport {
+    pub fn total(&self) -> usize {
+        self.line_endings + self.unicode + self.quotes + self.dashes + self.spaces + self.tabs + self.trailing
+    }
+}
+
+/// A line changed by normalization (endings not shown)
+#[derive(Debug, Clone, Serialize)]
+pub struct LineChange {
+    /// 1-based line number
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// Normalized content with what changed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Normalized {
+    pub text: String,
+    pub report: NormalizationReport,
+    /// Changed lines, up to [`MAX_CHANGES`]
+    pub changes: Vec<LineChange>,
+}
+
+/// Changed lines kept for the preview
+pub const MAX_CHANGES: usize = 500;
+
+/// Normalize `text` with `options`
+pub fn normalize(text: &str, options: &Normalization) -> Normalized {
+    let mut out = String::with_capacity(text.len());
+    let mut report = NormalizationReport::default();
+    let mut changes = Vec::new();
+
+    for (number, (line, ending)) in lines(text).enumerate() {
+        let after = normalize_line(line, options, &mut report);
+        if after != line && changes.len() < MAX_CHANGES {
+            changes.push(LineChange {
+                line: number + 1,
+                before: line.to_string(),
+                after: after.clone(),
+            });
+        }
+        out.push_str(&after);
+
+        let new_ending = match (options.line_endings, ending) {
+            (_, "") | (LineEndings::Keep, _) => ending,
+            (LineEndings::Lf, _) => "\n",
+            (LineEndings::Crlf, _) => "\r\n",
+        };
+        if new_ending != ending {
+            report.line_endings += 1;
+        }
+        out.push_str(new_ending);
+    }
+
+    Normalized {
+        text: out,
+        report,
+        changes,
+    }
+}
+
+/// Lines with their endings (`\n`, `\r\n`, `\r` or none for the last line)
+fn lines(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let line = match rest.find(['\r', '\n']) {
+            Some(end) => {
+                let len = if rest[end..].starts_with("\r\n") { 2 } else { 1 };
+                (&rest[..end], &rest[end..end + len])
+            }
+            None => (rest, ""),
+        };
+        rest = &rest[line.0.len() + line.1.len()..];
+        Some(line)
+    })
+}
+
+fn normalize_line(original: &str, options: &Normalization, report: &mut NormalizationReport) -> String {
+    let mut line: String = match options.unicode {
+        UnicodeForm::None => original.to_string(),
+        UnicodeForm::Nfc => original.nfc().collect(),
+        UnicodeForm::Nfkc => original.nfkc().collect(),
+    };
+    if line != original {
+        report.unicode += 1;
+    }
+
+    if options.fold_spaces || options.fold_quotes || options.fold_dashes {
+        let mut folded = String::with_capacity(line.len());
+        for c in line.chars() {
+            match c {
+                '\u{a0}' | '\u{2000}'..='\u{200a}' | '\u{202f}' | '\u{205f}' | '\u{3000}' if options.fold_spaces => {
+                    report.spaces += 1;
+                    folded.push(' ');
+                }
+                '\u{200b}' | '\u{2060}' | '\u{feff}' if options.fold_spaces => report.spaces += 1,
+                '‘' | '’' | '‚' | '‛' | '′' if options.fold_quotes => {
+                    report.quotes += 1;
+                    folded.push('\'');
+                }
+                '“' | '”' | '„' | '‟' | '″' if options.fold_quotes => {
+                    report.quotes += 1;
+                    folded.push('"');
+                }
+                '‐' | '‑' | '‒' | '–' | '−' if options.fold_dashes => {
+                    report.dashes += 1;
+                    folded.push('-');
+                }
+                '—' | '―' if options.fold_dashes => {
+                    report.dashes += 1;
+                    folded.push_str("--");
+                }
+                _ => folded.push(c),
+            }
+        }
+        line = folded;
+    }
+
+    if options.expand_tabs > 0 && line.contains('\t') {
+        let width = options.expand_tabs as usize;
+        let mut expanded = String::with_capacity(line.len() + width * 4);
+        let mut column = 0;
+        for c in line.chars() {
+            if c == '\t' {
+                report.tabs += 1;
+                let spaces = width - column % width;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            } else {
+                expanded.push(c);
+                column += 1;
+            }
+        }
+        line = expanded;
+    }
+
+    if options.trim_trailing {
+        let trimmed = line.trim_end().len();
+        report.trailing += line[trimmed..].chars().count();
+        line.truncate(trimmed);
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> Normalization {
+        Normalization {
+            line_endings: LineEndings::Lf,
+            unicode: UnicodeForm::Nfc,
+            fold_quotes: true,
+            fold_dashes: true,
+            fold_spaces: true,
+            expand_tabs: 4,
+            trim_trailing: true,
+        }
+    }
+
+    #[test]
+    fn the_default_changes_nothing() {
+        let text = "“a”\u{a0}—\tb  \r\nc\r";
+        let normalized = normalize(text, &Normalization::default());
+        assert!(Normalization::default().is_noop());
+        assert_eq!(normalized.text, text);
+        assert_eq!(normalized.report.total(), 0);
+        assert!(normalized.changes.is_empty());
+    }
+
+    #[test]
+    fn line_endings_convert_and_the_last_line_keeps_none() {
+        let options = Normalization { line_endings: LineEndings::Crlf, ..Normalization::default() };
+        let normalized = normalize("a\nb\r\nc\rd", &options);
+        assert_eq!(normalized.text, "a\r\nb\r\nc\r\nd");
+        assert_eq!(normalized.report.line_endings, 2);
+        // Endings alone do not list lines as changed
+        assert!(normalized.changes.is_empty());
+    }
+
+    #[test]
+    fn every_step_is_counted() {
+        let normalized = normalize("‘cafe\u{301}’ – “x”—y\u{200b}\u{a0}w\n\tz \t\n", &all());
+        assert_eq!(normalized.text, "'caf\u{e9}' - \"x\"--y w\n    z\n");
+        let report = normalized.report;
+        assert_eq!((report.unicode, report.quotes, report.dashes), (1, 4, 2));
+        assert_eq!((report.spaces, report.tabs, report.trailing), (2, 2, 3));
+    }
+
+    #[test]
+    fn tabs_expand_to_the_next_stop() {
+        let options = Normalization { expand_tabs: 4, ..Normalization::default() };
+        assert_eq!(normalize("ab\tc\t\td", &options).text, "ab  c       d");
+    }
+
+    #[test]
+    fn nfkc_also_replaces_compatibility_characters() {
+        let nfc = Normalization { unicode: UnicodeForm::Nfc, ..Normalization::default() };
+        let nfkc = Normalization { unicode: UnicodeForm::Nfkc, ..Normalization::default() };
+        assert_eq!(normalize("cafe\u{301} ﬁ…", &nfc).text, "caf\u{e9} ﬁ…");
+        assert_eq!(normalize("cafe\u{301} ﬁ…", &nfkc).text, "caf\u{e9} fi...");
+    }
+
+    #[test]
+    fn changed_lines_are_listed_with_their_number() {
+        let options = Normalization { trim_trailing: true, ..Normalization::default() };
+        let normalized = normalize("a\nb  \nc", &options);
+        assert_eq!(normalized.changes.len(), 1);
+        assert_eq!(
+            (normalized.changes[0].line, normalized.changes[0].before.as_str(), normalized.changes[0].after.as_str()),
+            (2, "b  ", "b")
+        );
+
+        let many = "x \n".repeat(MAX_CHANGES + 10);
+        assert_eq!(normalize(&many, &options).changes.len(), MAX_CHANGES);
+    }
+}
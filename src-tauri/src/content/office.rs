@@ -0,0 +1,188 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+use super::formats::{self, SourceFormat};
+
+/// Tell a DOCX from an ODT document (both are zip archives)
+pub fn detect(bytes: &[u8]) -> Result<SourceFormat, String> {
+    let archive = open(bytes)?;
+    if archive.index_for_name("word/document.xml").is_some() {
+        Ok(SourceFormat::Docx)
+    } else if archive.index_for_name("content.xml").is_some() {
+        Ok(SourceFormat::Odt)
+    } else {
+        Err("Unsupported archive: expected a DOCX or ODT document".to_string())
+    }
+}
+
+/// Text of a Word document, with a blank line between paragraphs
+pub fn docx_text(bytes: &[u8], max_mb: u32) -> Result<String, String> {
+    let xml = entry(bytes, "word/document.xml", max_mb)?;
+    let mut reader = Reader::from_str(&xml);
+    let mut out = String::new();
+    // Inside <w:t>; deleted text and field codes live in other elements
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event().map_err(|e| format!("Invalid DOCX: {}", e))? {
+            Event::Start(e) if e.name().as_ref() == b"w:t" => in_text = true,
+            Event::End(e) => match e.name().as_ref() {
+                b"w:t" => in_text = false,
+                b"w:p" => out.push_str("\n\n"),
+                b"w:tc" => out.push('\t'),
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"w:tab" => out.push('\t'),
+                b"w:br" | b"w:cr" => out.push('\n'),
+                b"w:noBreakHyphen" => out.push('-'),
+                _ => {}
+            },
+            Event::Text(t) if in_text => {
+                out.push_str(&t.unescape().map_err(|e| format!("Invalid DOCX: {}", e))?);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Text of an OpenDocument text file, with a blank line between paragraphs
+pub fn odt_text(bytes: &[u8], max_mb: u32) -> Result<String, String> {
+    let xml = entry(bytes, "content.xml", max_mb)?;
+    let mut reader = Reader::from_str(&xml);
+    let mut out = String::new();
+    // Depth of paragraphs and headings, and of footnotes to leave out
+    let mut paragraphs = 0usize;
+    let mut notes = 0usize;
+
+    loop {
+        match reader.read_event().map_err(|e| format!("Invalid ODT: {}", e))? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"text:p" | b"text:h" => paragraphs += 1,
+                b"text:note" => notes += 1,
+                _ => {}
+            },
+            Event::End(e) => match e.name().as_ref() {
+                b"text:p" | b"text:h" => {
+                    paragraphs = paragraphs.saturating_sub(1);
+                    if notes == 0 {
+                        out.push_str("\n\n");
+                    }
+                }
+                b"text:note" => notes = notes.saturating_sub(1),
+                b"table:table-cell" if notes == 0 => out.push('\t'),
+                _ => {}
+            },
+            Event::Empty(e) if paragraphs > 0 && notes == 0 => match e.name().as_ref() {
+                b"text:tab" => out.push('\t'),
+                b"text:line-break" => out.push('\n'),
+                b"text:s" => {
+                    // <text:s text:c="3"/> stands for several spaces
+                    let count = e
+                        .try_get_attribute("text:c")
+                        .ok()
+                        .flatten()
+                        .and_then(|a| std::str::from_utf8(&a.value).ok()?.parse().ok())
+                        .unwrap_or(1);
+                    if out.len().saturating_add(count) as u64 > formats::byte_limit(max_mb) {
+                        return Err(formats::unpacks_too_large(max_mb));
+                    }
+                    out.extend(std::iter::repeat_n(' ', count));
+                }
+                _ => {}
+            },
+            Event::Text(t) if paragraphs > 0 && notes == 0 => {
+                out.push_str(&t.unescape().map_err(|e| format!("Invalid ODT: {}", e))?);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+fn open(bytes: &[u8]) -> Result<ZipArchive<Cursor<&[u8]>>, String> {
+    ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Failed to open document: {}", e))
+}
+
+/// Contents of the archive member `name`, which may not unpack to more than
+/// `max_mb` megabytes (0 for no limit)
+fn entry(bytes: &[u8], name: &str, max_mb: u32) -> Result<String, String> {
+    let mut archive = open(bytes)?;
+    let file = archive
+        .by_name(name)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    // The size in the archive header is not trusted
+    let limit = formats::byte_limit(max_mb);
+    let mut xml = String::new();
+    file.take(limit.saturating_add(1))
+        .read_to_string(&mut xml)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    if xml.len() as u64 > limit {
+        return Err(formats::unpacks_too_large(max_mb));
+    }
+    Ok(xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    fn archive(name: &str, xml: &str) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.start_file(name, options).unwrap();
+        zip.write_all(xml.as_bytes()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn docx_paragraphs_tabs_and_breaks() {
+        let xml = r#"<w:document><w:body>
+            <w:p><w:r><w:t>Dear</w:t><w:tab/><w:t>Ada,</w:t></w:r></w:p>
+            <w:p><w:r><w:t>one</w:t><w:br/><w:t>two</w:t><w:delText>gone</w:delText></w:r></w:p>
+        </w:body></w:document>"#;
+        let bytes = archive("word/document.xml", xml);
+        assert_eq!(detect(&bytes), Ok(SourceFormat::Docx));
+        assert_eq!(docx_text(&bytes, 64).unwrap(), "Dear\tAda,\n\none\ntwo\n\n");
+    }
+
+    #[test]
+    fn odt_spaces_and_notes() {
+        let xml = r#"<office:document-content><office:body><office:text>
+            <text:h>Title</text:h>
+            <text:p>a<text:s text:c="3"/>b<text:note><text:p>footnote</text:p></text:note></text:p>
+        </office:text></office:body></office:document-content>"#;
+        let bytes = archive("content.xml", xml);
+        assert_eq!(detect(&bytes), Ok(SourceFormat::Odt));
+        assert_eq!(odt_text(&bytes, 64).unwrap(), "Title\n\na   b\n\n");
+    }
+
+    #[test]
+    fn member_unpacking_past_the_limit_is_refused() {
+        let xml = format!("<w:document><w:t>{}</w:t></w:document>", "a".repeat(2 * 1024 * 1024));
+        let bytes = archive("word/document.xml", &xml);
+        assert!(bytes.len() < 64 * 1024);
+        assert!(docx_text(&bytes, 1).is_err());
+        assert!(docx_text(&bytes, 0).is_ok());
+    }
+
+    #[test]
+    fn repeated_spaces_past_the_limit_are_refused() {
+        let xml = r#"<text:p><text:s text:c="18446744073709551615"/></text:p>"#;
+        assert!(odt_text(&archive("content.xml", xml), 1).is_err());
+    }
+
+    #[test]
+    fn other_archives_are_rejected() {
+        assert!(detect(&archive("mimetype", "application/zip")).is_err());
+        assert!(detect(b"PK\x03\x04 not really a zip").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! PDF text extraction in a child process.
+//!
+//! pdf-extract panics on many malformed or unusual PDFs, and release builds
+//! abort on panic, so it never runs in the process that loads the file. The
+//! binary re-runs itself with [`HELPER_ARG`], writes the PDF to the helper's
+//! stdin and reads the text from its stdout. A helper that crashes is
+//! reported as an unreadable PDF. The helper only exists in the binaries, so
+//! it is tested through the CLI (`tests/pdf.rs`).
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+use super::formats;
+
+/// Argument that starts a binary as the PDF helper
+pub const HELPER_ARG: &str = "--extract-pdf-helper";
+
+/// Run as the PDF helper and exit if the process was started with
+/// [`HELPER_ARG`]; return otherwise. Binaries call this first thing in `main`.
+pub fn run_helper_if_requested() {
+    if std::env::args().nth(1).as_deref() != Some(HELPER_ARG) {
+        return;
+    }
+    let mut bytes = Vec::new();
+    if let Err(e) = std::io::stdin().read_to_end(&mut bytes) {
+        eprint!("{}", e);
+        std::process::exit(1);
+    }
+    match pdf_extract::extract_text_from_mem(&bytes) {
+        Ok(text) => {
+            let mut stdout = std::io::stdout().lock();
+            let written = stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush());
+            std::process::exit(if written.is_ok() { 0 } else { 1 });
+        }
+        Err(e) => {
+            eprint!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Text of a PDF, extracted by a helper process. The text may not be more
+/// than `max_mb` megabytes (0 for no limit).
+pub fn text(bytes: &[u8], max_mb: u32) -> Result<String, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to read PDF: {}", e))?;
+    let mut child = Command::new(exe)
+        .arg(HELPER_ARG)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to read PDF: {}", e))?;
+
+    // Feed stdin and drain stderr while stdout is read so no pipe fills up
+    let limit = formats::byte_limit(max_mb);
+    let (stdin, stdout, stderr) = (child.stdin.take(), child.stdout.take(), child.stderr.take());
+    let (text, errors) = std::thread::scope(|scope| {
+        scope.spawn(move || {
+            if let Some(mut stdin) = stdin {
+                // A helper that exits early closes the pipe; its status says why
+                let _ = stdin.write_all(bytes);
+            }
+        });
+        let errors = scope.spawn(move || {
+            let mut errors = Vec::new();
+            if let Some(stderr) = stderr {
+                let _ = stderr.take(64 * 1024).read_to_end(&mut errors);
+            }
+            errors
+        });
+        let mut text = Vec::new();
+        let read = match stdout {
+            Some(stdout) => stdout.take(limit.saturating_add(1)).read_to_end(&mut text),
+            None => Ok(0),
+        };
+        if read.is_err() || text.len() as u64 > limit {
+            // Stop a helper that is still writing
+            let _ = child.kill();
+        }
+        (read.map(|_| text), errors.join().unwrap_or_default())
+    });
+    let status = child.wait().map_err(|e| format!("Failed to read PDF: {}", e))?;
+    let text = text.map_err(|e| format!("Failed to read PDF: {}", e))?;
+
+    if text.len() as u64 > limit {
+        return Err(formats::unpacks_too_large(max_mb));
+    }
+    if !status.success() {
+        let message = String::from_utf8_lossy(&errors);
+        return Err(match message.trim() {
+            "" => "Failed to read PDF: the file is damaged or not supported".to_string(),
+            message => format!("Failed to read PDF: {}", message.lines().last().unwrap_or(message)),
+        });
+    }
+    String::from_utf8(text).map_err(|_| "Failed to read PDF: the text is not UTF-8".to_string())
+}
@@ -0,0 +1,175 @@
+use super::formats::latin1;
+
+/// Destinations holding something other than document text
+const SKIPPED: &[&str] = &[
+    "fonttbl", "colortbl", "stylesheet", "info", "pict", "header", "headerl", "headerr", "headerf",
+    "footer", "footerl", "footerr", "footerf", "footnote", "themedata", "colorschememapping",
+    "datastore", "latentstyles", "listtable", "listoverridetable", "rsidtbl", "generator", "xmlnstbl",
+    "mmathPr", "object", "fldinst", "filetbl", "revtbl", "pgdsctbl", "shp", "nonshppict",
+];
+
+#[derive(Clone, Copy)]
+struct Group {
+    skip: bool,
+    /// Fallback characters following a \u escape (\ucN)
+    unicode_skip: usize,
+}
+
+/// Plain text of an RTF document, with a blank line between paragraphs
+pub fn to_text(rtf: &str) -> String {
+    let chars: Vec<char> = rtf.chars().collect();
+    let mut out = String::with_capacity(rtf.len() / 2);
+    let mut stack: Vec<Group> = Vec::new();
+    let mut group = Group {
+        skip: false,
+        unicode_skip: 1,
+    };
+    // Fallback characters still to drop after a \u escape
+    let mut pending_skip = 0;
+    // Whether the current group has just opened (for \* destinations)
+    let mut group_start = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '{' => {
+                stack.push(group);
+                group_start = true;
+                i += 1;
+                continue;
+            }
+            '}' => {
+                group = stack.pop().unwrap_or(group);
+                pending_skip = 0;
+                i += 1;
+            }
+            '\\' => {
+                let (word, param, next) = control(&chars, i + 1);
+                i = next;
+                if group.skip {
+                    group_start = false;
+                    continue;
+                }
+                let emit = |out: &mut String, text: &str, pending: &mut usize| {
+                    if *pending > 0 {
+                        *pending -= 1;
+                    } else {
+                        out.push_str(text);
+                    }
+                };
+                match word.as_str() {
+                    "*" if group_start => group.skip = true,
+                    w if group_start && SKIPPED.contains(&w) => group.skip = true,
+                    "par" | "sect" => out.push_str("\n\n"),
+                    "line" | "row" => out.push('\n'),
+                    "tab" | "cell" => out.push('\t'),
+                    "emdash" => out.push('—'),
+                    "endash" => out.push('–'),
+                    "lquote" => out.push('‘'),
+                    "rquote" => out.push('’'),
+                    "ldblquote" => out.push('“'),
+                    "rdblquote" => out.push('”'),
+                    "bullet" => out.push('•'),
+                    "~" => out.push('\u{a0}'),
+                    "_" => out.push('-'),
+                    "\\" | "{" | "}" => emit(&mut out, &word, &mut pending_skip),
+                    "'" => {
+                        let hex: String = chars[i..].iter().take(2).collect();
+                        i += hex.len();
+                        if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                            emit(&mut out, &latin1(byte).to_string(), &mut pending_skip);
+                        }
+                    }
+                    "u" => {
+                        if let Some(code) = param {
+                            // Negative values wrap to code points above 0x7FFF
+                            let code = if code < 0 { code + 0x10000 } else { code };
+                            if let Some(c) = char::from_u32(code as u32) {
+                                out.push(c);
+                            }
+                            pending_skip = group.unicode_skip;
+                        }
+                    }
+                    "uc" => group.unicode_skip = param.unwrap_or(1).max(0) as usize,
+                    _ => {}
+                }
+            }
+            '\r' | '\n' => i += 1,
+            _ => {
+                if !group.skip {
+                    if pending_skip > 0 {
+                        pending_skip -= 1;
+                    } else {
+                        out.push(c);
+                    }
+                }
+                i += 1;
+            }
+        }
+        group_start = false;
+    }
+    out
+}
+
+/// Parse the control word or symbol after a backslash at `chars[start]`:
+/// its name, numeric parameter and the index after it
+fn control(chars: &[char], start: usize) -> (String, Option<i32>, usize) {
+    let Some(&first) = chars.get(start) else {
+        return (String::new(), None, start);
+    };
+    if !first.is_ascii_alphabetic() {
+        // Control symbol; an escaped line break is a paragraph
+        let name = if matches!(first, '\n' | '\r') { "par".to_string() } else { first.to_string() };
+        return (name, None, start + 1);
+    }
+
+    let mut i = start;
+    while i < chars.len() && chars[i].is_ascii_alphabetic() {
+        i += 1;
+    }
+    let word: String = chars[start..i].iter().collect();
+
+    let digits_start = i;
+    if chars.get(i) == Some(&'-') {
+        i += 1;
+    }
+    while i < chars.len() && chars[i].is_ascii_digit() {
+        i += 1;
+    }
+    let param = chars[digits_start..i].iter().collect::<String>().parse().ok();
+
+    // A single space delimits the control word and is not text
+    if chars.get(i) == Some(&' ') {
+        i += 1;
+    }
+    (word, param, i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paragraphs_and_skipped_destinations() {
+        let rtf = "{\\rtf1\\ansi{\\fonttbl{\\f0 Arial;}}{\\*\\generator Writer;}\\f0\\fs24 Hello\\par\nWorld\\tab!\\line end}";
+        assert_eq!(to_text(rtf), "Hello\n\nWorld\t!\nend");
+    }
+
+    #[test]
+    fn escapes_and_hex_characters() {
+        assert_eq!(to_text("{\\rtf1 caf\\'e9 \\{x\\} a\\\\b\\emdash c}"), "café {x} a\\b—c");
+    }
+
+    #[test]
+    fn unicode_escapes_drop_their_fallback() {
+        assert_eq!(to_text("{\\rtf1 it\\u8217?s}"), "it’s");
+        assert_eq!(to_text("{\\rtf1\\uc2 a\\u-3913??b}"), "a\u{f0b7}b");
+        assert_eq!(to_text("{\\rtf1\\uc0 \\u8364 5}"), "€5");
+    }
+
+    #[test]
+    fn fallback_does_not_leak_out_of_a_group() {
+        assert_eq!(to_text("{\\rtf1 {\\uc2\\u8364}x}"), "€x");
+    }
+}
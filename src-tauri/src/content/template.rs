@@ -0,0 +1,248 @@
+//! `{{name}}` placeholders filled in just before typing.
+
+use chrono::Local;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{formats, Shortcut};
+
+/// Variables with a value even when none is given
+pub const BUILTINS: &[&str] = &["date", "time", "datetime", "year", "weekday"];
+
+/// Longest placeholder body (name and padding) looked for; a `{{` with no
+/// `}}` within this many characters on the same line is plain text
+const MAX_NAME_CHARS: usize = 64;
+
+/// A `{{name}}` placeholder at `start..end` (byte offsets)
+struct Placeholder {
+    start: usize,
+    end: usize,
+    name: String,
+}
+
+/// Placeholders in `text`, plus the byte offsets of `\{{` escapes. Works on
+/// the bytes: the delimiters are ASCII, which never occurs inside a
+/// multi-byte character.
+fn scan(text: &str) -> (Vec<Placeholder>, Vec<usize>) {
+    let bytes = text.as_bytes();
+    let mut placeholders = Vec::new();
+    let mut escapes = Vec::new();
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        if bytes[i] == b'\\' && bytes[i + 1] == b'{' && bytes.get(i + 2) == Some(&b'{') {
+            escapes.push(i);
+            i += 3;
+            continue;
+        }
+        if bytes[i] != b'{' || bytes[i + 1] != b'{' {
+            i += 1;
+            continue;
+        }
+        let body = &text[i + 2..];
+        let close = body
+            .char_indices()
+            .take(MAX_NAME_CHARS)
+            .take_while(|&(_, c)| c != '\n')
+            .find(|&(j, _)| body[j..].starts_with("}}"))
+            .map(|(j, _)| i + 2 + j);
+        let name = close.map(|j| text[i + 2..j].trim());
+        match (close, name) {
+            (Some(j), Some(name)) if is_name(name) => {
+                placeholders.push(Placeholder {
+                    start: i,
+                    end: j + 2,
+                    name: name.to_string(),
+                });
+                i = j + 2;
+            }
+            _ => i += 2,
+        }
+    }
+    (placeholders, escapes)
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Names of the variables `text` needs values for, in order of first use.
+/// Built-in variables are left out.
+pub fn variables(text: &str) -> Vec<String> {
+    if !text.contains("{{") {
+        return Vec::new();
+    }
+    let mut names: Vec<String> = Vec::new();
+    for placeholder in scan(text).0 {
+        if !BUILTINS.contains(&placeholder.name.as_str()) && !names.contains(&placeholder.name) {
+            names.push(placeholder.name);
+        }
+    }
+    names
+}
+
+/// Variables in `needed` without a value in `values`
+pub fn missing(needed: &[String], values: &HashMap<String, String>) -> Vec<String> {
+    needed.iter().filter(|name| !values.contains_key(*name)).cloned().collect()
+}
+
+fn builtin(name: &str) -> Option<String> {
+    let now = Local::now();
+    let format = match name {
+        "date" => "%Y-%m-%d",
+        "time" => "%H:%M",
+        "datetime" => "%Y-%m-%d %H:%M",
+        "year" => "%Y",
+        "weekday" => "%A",
+        _ => return None,
+    };
+    Some(now.format(format).to_string())
+}
+
+/// Fill in the placeholders of `text` from `values` (which take precedence
+/// over built-ins), moving `shortcuts` along with the text around them.
+/// Fails listing every variable without a value, or if the result would be
+/// over `max_mb` megabytes (0 for no limit). Text without placeholders is
+/// shared, not copied.
+pub fn render(
+    text: &Arc<str>,
+    values: &HashMap<String, String>,
+    shortcuts: &mut [Shortcut],
+    max_mb: u32,
+) -> Result<Arc<str>, String> {
+    if !text.contains("{{") {
+        return Ok(text.clone());
+    }
+    let (placeholders, escapes) = scan(text);
+
+    let missing: Vec<&str> = placeholders
+        .iter()
+        .map(|p| p.name.as_str())
+        .filter(|name| !values.contains_key(*name) && !BUILTINS.contains(name))
+        .fold(Vec::new(), |mut names, name| {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+            names
+        });
+    if !missing.is_empty() {
+        return Err(format!("Missing template variables: {}", missing.join(", ")));
+    }
+
+    // (start, end, replacement) in order, as byte offsets
+    let mut edits: Vec<(usize, usize, String)> = placeholders
+        .into_iter()
+        .map(|p| {
+            let value = values.get(&p.name).cloned().or_else(|| builtin(&p.name)).unwrap_or_default();
+            (p.start, p.end, value)
+        })
+        .chain(escapes.into_iter().map(|i| (i, i + 3, "{{".to_string())))
+        .collect();
+    edits.sort_by_key(|edit| edit.0);
+
+    let limit = formats::byte_limit(max_mb);
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    // Character index in `text` of `last`, and change in length so far
+    let mut index = 0;
+    let mut shift: isize = 0;
+    let mut next_shortcut = 0;
+    for (start, end, value) in &edits {
+        let before = &text[last..*start];
+        out.push_str(before);
+        let start_index = index + before.chars().count();
+        let end_index = start_index + text[*start..*end].chars().count();
+        // Shortcuts before the placeholder, or inside it (moved to its start)
+        while let Some(shortcut) = shortcuts.get_mut(next_shortcut).filter(|s| s.index < end_index) {
+            shortcut.index = (shortcut.index.min(start_index) as isize + shift) as usize;
+            next_shortcut += 1;
+        }
+        out.push_str(value);
+        if out.len() as u64 > limit {
+            return Err(format!("The filled-in template is over the {} MB limit (maxFileMb)", max_mb));
+        }
+        shift += value.chars().count() as isize - (end_index - start_index) as isize;
+        last = *end;
+        index = end_index;
+    }
+    out.push_str(&text[last..]);
+    formats::check_size(out.len() as u64, max_mb)?;
+    for shortcut in &mut shortcuts[next_shortcut..] {
+        shortcut.index = (shortcut.index as isize + shift) as usize;
+    }
+    Ok(out.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::typer::keyboard::KeyCombo;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn shortcut(index: usize) -> Shortcut {
+        Shortcut {
+            index,
+            keys: KeyCombo::parse("Ctrl+B").unwrap(),
+        }
+    }
+
+    #[test]
+    fn variables_in_order_without_builtins_or_repeats() {
+        let text = "{{ name }} on {{date}}, {{company}} and {{name}}";
+        assert_eq!(variables(text), ["name", "company"]);
+    }
+
+    #[test]
+    fn invalid_unclosed_and_escaped_placeholders_are_text() {
+        assert!(variables("{{1st}} {{a b}} {{open\n}} \\{{name}}").is_empty());
+        let long = format!("{{{{{}}}}}", "x".repeat(MAX_NAME_CHARS + 1));
+        assert!(variables(&long).is_empty());
+    }
+
+    #[test]
+    fn render_fills_values_and_unescapes() {
+        let text: Arc<str> = "Dear {{name}}, see \\{{name}}".into();
+        let out = render(&text, &values(&[("name", "Ada")]), &mut [], 0).unwrap();
+        assert_eq!(&*out, "Dear Ada, see {{name}}");
+    }
+
+    #[test]
+    fn render_lists_every_missing_variable() {
+        let text: Arc<str> = "{{a}} {{b}} {{a}}".into();
+        assert_eq!(
+            render(&text, &HashMap::new(), &mut [], 0).unwrap_err(),
+            "Missing template variables: a, b"
+        );
+    }
+
+    #[test]
+    fn render_shares_text_without_placeholders() {
+        let text: Arc<str> = "plain".into();
+        let out = render(&text, &HashMap::new(), &mut [], 0).unwrap();
+        assert!(Arc::ptr_eq(&text, &out));
+    }
+
+    #[test]
+    fn shortcuts_move_with_the_text_around_them() {
+        // Character indices, with multi-byte characters before and after
+        let text: Arc<str> = "é {{name}} ü x".into();
+        let mut shortcuts = [shortcut(0), shortcut(4), shortcut(11), shortcut(13)];
+        let out = render(&text, &values(&[("name", "Zoë")]), &mut shortcuts, 0).unwrap();
+        assert_eq!(&*out, "é Zoë ü x");
+        let indices: Vec<usize> = shortcuts.iter().map(|s| s.index).collect();
+        // Inside the placeholder moves to its start; after it shifts by 3 - 8
+        assert_eq!(indices, [0, 2, 6, 8]);
+    }
+
+    #[test]
+    fn render_refuses_output_over_the_limit() {
+        let text: Arc<str> = "{{big}}".into();
+        let big = "x".repeat(2 * 1024 * 1024);
+        assert!(render(&text, &values(&[("big", &big)]), &mut [], 1).is_err());
+        assert!(render(&text, &values(&[("big", &big)]), &mut [], 0).is_ok());
+    }
+}
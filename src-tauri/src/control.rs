@@ -0,0 +1,678 @@
+//! Local control API.
+//!
+//! Lets other tools (test harnesses, stream deck scripts, editor plugins) drive
+//! the engine. The endpoint is a Unix socket at `$XDG_RUNTIME_DIR/ghostkeys.sock`
+//! (falling back to `ghostkeys-$USER/ghostkeys.sock` in the temp directory) on
+//! Unix and `127.0.0.1:47400` elsewhere. Set `GHOSTKEYS_CONTROL` to another
+//! socket path / address, or to `off`. A second instance does not start the
+//! API while the first one answers on the socket.
+//!
+//! The socket is only accessible to the user. The TCP endpoint is reachable by
+//! any local process, so each run writes a random token to
+//! `%LOCALAPPDATA%\ghostkeys\control.token` (readable by the user only) and
+//! every request must carry it as `"token"`. Connections that send an HTTP
+//! request line or header are closed, so web pages cannot reach the endpoint.
+//!
+//! The protocol is newline-delimited JSON. Each request is one line, at most
+//! `maxFileMb` plus 64 KiB long:
+//!
+//! ```json
+//! {"id": 1, "method": "start", "params": {}}
+//! ```
+//!
+//! (with `"token": "..."` added on the TCP endpoint)
+//!
+//! and gets exactly one response line with the same `id`:
+//!
+//! ```json
+//! {"id": 1, "ok": true, "result": null}
+//! {"id": 1, "ok": false, "error": "Already typing"}
+//! ```
+//!
+//! Methods:
+//!
+//! | method        | params                                  | result                  |
+//! |---------------|-----------------------------------------|-------------------------|
+//! | `load_content`| `{"content": "...", "name": "..."}`     | `null`                  |
+//! | `load_file`   | `{"path": "..."}`                       | file info               |
+//! | `type_clipboard` | (loads the clipboard text and starts typing) | file info          |
+//! | `preview_normalization` | `{"content": "...", "options": {...}}` (options optional) | normalized text, counts and changed lines |
+//! | `enqueue`     | `{"content": "...", "name": "...", "profile": "fast", "trigger": {"type": "hotkey"}}` or `{"path": "..."}` instead of content | queue item |
+//! | `get_queue`   |                                         | queue items             |
+//! | `remove_queue_item` | `{"id": 2}`                       | `null`                  |
+//! | `move_queue_item`   | `{"id": 2, "index": 0}`           | `null`                  |
+//! | `clear_queue` |                                         | `null`                  |
+//! | `continue`    | (releases a run waiting for the hotkey) | `null`                  |
+//! | `get_config`  |                                         | config                  |
+//! | `set_config`  | `{"config": {...}}` (same as the app)   | `null`                  |
+//! | `get_missing_variables` | (template variables without a value) | names               |
+//! | `start`       | `{"variables": {"name": "Ada"}}` (optional template values) | `null` |
+//! | `stop`        |                                         | `null`                  |
+//! | `abort`       | `{"undo": true}` (backspace what was typed) | `null`              |
+//! | `pause`       |                                         | `null`                  |
+//! | `resume`      |                                         | `null`                  |
+//! | `speed_up`    |                                         | new WPM                 |
+//! | `speed_down`  |                                         | new WPM                 |
+//! | `get_state`   |                                         | same as `get_state`     |
+//! | `get_session_report` |                                  | last run's report or `null` |
+//! | `export_keystroke_log` | `{"path": "...", "format": "jsonl"}` (or `"csv"`) | records written |
+//! | `calibrate`   | `{"path": "...", "reference": "..."}` or `{"keys": [{"tMs": 0, "kind": "type", "char": "a"}]}` | fitted config, digraphs and report |
+//! | `subscribe`   | `{"events": ["typing-progress"]}` (optional filter) | `null`      |
+//!
+//! After `subscribe`, engine events are pushed on the same connection as
+//! `{"event": "typing-progress", "payload": {...}}` lines, interleaved with
+//! responses. Events are the same as the UI receives: `typing-progress`,
+//! `typing-state-changed`, `countdown-tick`, `typing-error`, `typing-undone`,
+//! `config-changed`, `queue-changed`, `queue-item-started`, `queue-item-finished`,
+//! `trigger-waiting`, `trigger-tick`, `trigger-fired`, `break-started`,
+//! `break-ended`, `focus-lost`, `focus-restored`, `focus-guard-unavailable`
+//! (once per run, on platforms without window detection),
+//! `variables-required`, `content-loaded`, `content-changed` and
+//! `session-report`.
+//!
+//! With `watchFile` in the config, a file loaded with `load_file` is reloaded
+//! when it changes and `content-changed` carries `{"applied": true, "diff":
+//! {...}, "file": {...}}`. A change during a run is held back until it ends
+//! (`"applied": false` with a message). A file that cannot be read is
+//! reported as `"applied": false` with the error as the message and no diff.
+//!
+//! Content from every source (`load_content`, `load_file`, `type_clipboard`
+//! and `enqueue`) is limited to `maxFileMb`. The `content` of a file info is
+//! a preview of the first 100,000 characters; `truncated` is true when the
+//! document is longer.
+//!
+//! A trigger is `{"type": "countdown", "seconds": 5}`, `{"type": "hotkey"}`,
+//! `{"type": "windowFocus", "title": "Notepad"}` or `{"type": "at", "unixMs": ...}`.
+
+use crate::config::Config;
+use crate::content::normalize::Normalization;
+use crate::typer::calibrate::RecordedKey;
+use crate::typer::stats::LogFormat;
+use crate::typer::trigger::StartTrigger;
+use crate::{engine, QueueSource};
+use crate::typer::events::EventSink;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// Environment variable overriding the endpoint
+pub const ENDPOINT_ENV: &str = "GHOSTKEYS_CONTROL";
+
+/// Room for the method and parameters on top of the content of a request
+const MAX_REQUEST_OVERHEAD: u64 = 64 * 1024;
+
+#[cfg(not(unix))]
+const DEFAULT_ADDR: &str = "127.0.0.1:47400";
+
+/// Request lines a browser sends, which are never valid requests here
+const HTTP_PREFIXES: [&str; 10] = [
+    "GET ", "POST ", "PUT ", "HEAD ", "OPTIONS ", "DELETE ", "PATCH ", "CONNECT ", "TRACE ", "HOST:",
+];
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    #[serde(default)]
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoadContentParams {
+    content: String,
+    #[serde(default = "default_content_name")]
+    name: String,
+}
+
+fn default_content_name() -> String {
+    "Control API".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct LoadFileParams {
+    path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviewNormalizationParams {
+    content: String,
+    #[serde(default)]
+    options: Option<Normalization>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnqueueParams {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default = "default_content_name")]
+    name: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    profile: Option<String>,
+    #[serde(default)]
+    config: Option<Config>,
+    #[serde(default)]
+    trigger: Option<StartTrigger>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueueItemParams {
+    id: u64,
+    #[serde(default)]
+    index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetConfigParams {
+    config: Config,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportLogParams {
+    path: String,
+    format: LogFormat,
+}
+
+#[derive(Debug, Deserialize)]
+struct CalibrateParams {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    keys: Option<Vec<RecordedKey>>,
+    #[serde(default)]
+    reference: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StartParams {
+    #[serde(default)]
+    variables: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AbortParams {
+    #[serde(default)]
+    undo: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SubscribeParams {
+    #[serde(default)]
+    events: Option<Vec<String>>,
+}
+
+/// Listen on the control endpoint until the app exits
+pub async fn serve<S: EventSink + Clone>(events: S) -> std::io::Result<()> {
+    let endpoint = std::env::var(ENDPOINT_ENV).ok();
+    if endpoint.as_deref() == Some("off") {
+        return Ok(());
+    }
+    listen(endpoint, events).await
+}
+
+#[cfg(unix)]
+async fn listen<S: EventSink + Clone>(endpoint: Option<String>, events: S) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::UnixListener;
+
+    let path = match endpoint {
+        Some(path) => std::path::PathBuf::from(path),
+        None => default_socket_dir()?.join("ghostkeys.sock"),
+    };
+
+    // Never take over the endpoint of a running instance; only a stale socket
+    // from a previous run is removed
+    if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AddrInUse,
+            format!("another instance is serving the control API at {}", path.display()),
+        ));
+    }
+    if is_socket(&path) {
+        std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(handle_connection(stream, events.clone(), None));
+    }
+}
+
+/// Directory for the default socket. `$XDG_RUNTIME_DIR` is private to the
+/// user; the shared temp directory is not, so the socket goes in a
+/// subdirectory only the user can enter and never exists with looser
+/// permissions.
+#[cfg(unix)]
+fn default_socket_dir() -> std::io::Result<std::path::PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Ok(dir.into());
+    }
+    let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+    let dir = std::env::temp_dir().join(format!("ghostkeys-{}", user));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+    // Someone else may have created it first
+    let meta = std::fs::symlink_metadata(&dir)?;
+    if !meta.is_dir() || meta.permissions().mode() & 0o077 != 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is not a private directory", dir.display()),
+        ));
+    }
+    Ok(dir)
+}
+
+#[cfg(unix)]
+fn is_socket(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket())
+}
+
+#[cfg(not(unix))]
+async fn listen<S: EventSink + Clone>(endpoint: Option<String>, events: S) -> std::io::Result<()> {
+    use tokio::net::TcpListener;
+
+    let addr = endpoint.unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let listener = TcpListener::bind(&addr).await?;
+    if !listener.local_addr()?.ip().is_loopback() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "control API must bind to a loopback address",
+        ));
+    }
+    let token: std::sync::Arc<str> = write_token()?.into();
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(handle_connection(stream, events.clone(), Some(token.clone())));
+    }
+}
+
+/// Generate this run's token and write it where only the user can read it
+#[cfg(not(unix))]
+fn write_token() -> std::io::Result<String> {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    let token: String = (0..32).map(|_| format!("{:02x}", rng.gen::<u8>())).collect();
+
+    // The profile's local app data (and the per-user temp directory under it)
+    // is only accessible to the user
+    let dir = std::env::var_os("LOCALAPPDATA")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("ghostkeys");
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join("control.token"), &token)?;
+    Ok(token)
+}
+
+/// Whether a line is an HTTP request line or header
+fn looks_like_http(line: &str) -> bool {
+    let line = line.trim_start().as_bytes();
+    HTTP_PREFIXES.iter().any(|prefix| {
+        line.get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix.as_bytes()))
+    })
+}
+
+/// Whether a request carries the expected token, compared in constant time
+fn authorized(expected: Option<&str>, request: &Request) -> bool {
+    let Some(expected) = expected else {
+        return true;
+    };
+    let given = request.token.as_deref().unwrap_or_default();
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn handle_connection<T, S>(stream: T, events: S, token: Option<std::sync::Arc<str>>)
+where
+    T: AsyncRead + AsyncWrite + Send + 'static,
+    S: EventSink + Clone,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<String>();
+
+    // Single writer so responses and pushed events never interleave mid-line
+    let writer_task = tokio::spawn(async move {
+        while let Some(mut line) = out_rx.recv().await {
+            line.push('\n');
+            if writer.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    let mut forwarder: Option<JoinHandle<()>> = None;
+
+    loop {
+        // Content is sent inline, so a request may be as long as the largest
+        // content allowed, but no longer
+        let limit = crate::content::formats::byte_limit(engine().get_config().max_file_mb)
+            .saturating_add(MAX_REQUEST_OVERHEAD);
+        buf.clear();
+        match (&mut reader).take(limit).read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if !buf.ends_with(b"\n") && buf.len() as u64 >= limit {
+            let error = serde_json::json!({
+                "id": Value::Null,
+                "ok": false,
+                "error": "Request too long (maxFileMb)",
+            });
+            let _ = out_tx.send(error.to_string());
+            break;
+        }
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() {
+            continue;
+        }
+        if looks_like_http(line) {
+            break;
+        }
+
+        let response = match serde_json::from_str::<Request>(line) {
+            Ok(request) => {
+                let id = request.id.clone();
+                let result = if !authorized(token.as_deref(), &request) {
+                    Err("Invalid token".to_string())
+                } else if request.method == "subscribe" {
+                    if forwarder.is_some() {
+                        Err("Already subscribed".to_string())
+                    } else {
+                        subscribe(request.params, out_tx.clone()).map(|task| {
+                            forwarder = Some(task);
+                            Value::Null
+                        })
+                    }
+                } else {
+                    // Requests read files and extract documents, so keep them
+                    // off the async workers
+                    let events = events.clone();
+                    tokio::task::spawn_blocking(move || {
+                        dispatch(&request.method, request.params, &events)
+                    })
+                    .await
+                    .unwrap_or_else(|e| Err(format!("Request failed: {}", e)))
+                };
+                match result {
+                    Ok(result) => serde_json::json!({ "id": id, "ok": true, "result": result }),
+                    Err(e) => serde_json::json!({ "id": id, "ok": false, "error": e }),
+                }
+            }
+            Err(e) => serde_json::json!({
+                "id": Value::Null,
+                "ok": false,
+                "error": format!("Invalid request: {}", e),
+            }),
+        };
+
+        if out_tx.send(response.to_string()).is_err() {
+            break;
+        }
+    }
+
+    if let Some(task) = forwarder {
+        task.abort();
+    }
+    drop(out_tx);
+    let _ = writer_task.await;
+}
+
+/// Run a request against the engine
+fn dispatch<S: EventSink + Clone>(method: &str, params: Value, events: &S) -> Result<Value, String> {
+    match method {
+        "load_content" => {
+            let p: LoadContentParams = parse_params(params)?;
+            crate::load_text(p.name, p.content).map(|_| Value::Null)
+        }
+        "load_file" => {
+            let p: LoadFileParams = parse_params(params)?;
+            let info = crate::load_file(events.clone(), &p.path)?;
+            serde_json::to_value(info).map_err(|e| e.to_string())
+        }
+        "type_clipboard" => {
+            let info = crate::type_clipboard(events.clone())?;
+            serde_json::to_value(info).map_err(|e| e.to_string())
+        }
+        "preview_normalization" => {
+            let p: PreviewNormalizationParams = parse_params(params)?;
+            let preview = crate::preview_normalization(&p.content, p.options);
+            serde_json::to_value(preview).map_err(|e| e.to_string())
+        }
+        "enqueue" => {
+            let p: EnqueueParams = parse_params(params)?;
+            let source = match (p.content, p.path) {
+                (Some(content), None) => QueueSource::Text {
+                    content,
+                    name: p.name,
+                },
+                (None, Some(path)) => QueueSource::File { path },
+                _ => return Err("Pass exactly one of content or path".to_string()),
+            };
+            let item = crate::enqueue(events, source, p.profile, p.config, p.trigger)?;
+            serde_json::to_value(item).map_err(|e| e.to_string())
+        }
+        "get_queue" => serde_json::to_value(engine().get_queue()).map_err(|e| e.to_string()),
+        "remove_queue_item" => {
+            let p: QueueItemParams = parse_params(params)?;
+            crate::remove_queue_item(events, p.id).map(|_| Value::Null)
+        }
+        "move_queue_item" => {
+            let p: QueueItemParams = parse_params(params)?;
+            crate::move_queue_item(events, p.id, p.index).map(|_| Value::Null)
+        }
+        "clear_queue" => crate::clear_queue(events).map(|_| Value::Null),
+        "continue" => crate::continue_queue().map(|_| Value::Null),
+        "get_config" => serde_json::to_value(engine().get_config()).map_err(|e| e.to_string()),
+        "set_config" => {
+            let p: SetConfigParams = parse_params(params)?;
+            crate::set_config(p.config)?;
+            Ok(Value::Null)
+        }
+        "get_missing_variables" => {
+            serde_json::to_value(crate::get_missing_variables()).map_err(|e| e.to_string())
+        }
+        "start" => {
+            let p: StartParams = parse_params_or_default(params)?;
+            crate::start_typing(events.clone(), p.variables).map(|_| Value::Null)
+        }
+        "stop" => {
+            crate::stop_typing(events);
+            Ok(Value::Null)
+        }
+        "abort" => {
+            let p: AbortParams = parse_params_or_default(params)?;
+            crate::abort_typing(events, p.undo);
+            Ok(Value::Null)
+        }
+        "pause" => {
+            crate::pause_typing(events);
+            Ok(Value::Null)
+        }
+        "resume" => {
+            crate::resume_typing(events);
+            Ok(Value::Null)
+        }
+        "speed_up" => Ok(crate::adjust_speed(events, 1).into()),
+        "speed_down" => Ok(crate::adjust_speed(events, -1).into()),
+        "get_state" => Ok(crate::get_state()),
+        "get_session_report" => {
+            serde_json::to_value(crate::get_session_report()).map_err(|e| e.to_string())
+        }
+        "export_keystroke_log" => {
+            let p: ExportLogParams = parse_params(params)?;
+            Ok(crate::export_keystroke_log(&p.path, p.format)?.into())
+        }
+        "calibrate" => {
+            let p: CalibrateParams = parse_params(params)?;
+            let calibration = match (p.keys, p.path) {
+                (Some(keys), _) => crate::calibrate_keys(&keys, p.reference.as_deref())?,
+                (None, Some(path)) => crate::calibrate_file(&path, p.reference.as_deref())?,
+                (None, None) => return Err("Expected keys or path".to_string()),
+            };
+            serde_json::to_value(calibration).map_err(|e| e.to_string())
+        }
+        _ => Err(format!("Unknown method: {}", method)),
+    }
+}
+
+/// Start forwarding engine events to this connection
+fn subscribe(params: Value, out_tx: mpsc::UnboundedSender<String>) -> Result<JoinHandle<()>, String> {
+    let p: SubscribeParams = parse_params_or_default(params)?;
+
+    let mut rx = engine().subscribe();
+    Ok(tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    let wanted = p
+                        .events
+                        .as_ref()
+                        .is_none_or(|names| names.contains(&event.event));
+                    if !wanted {
+                        continue;
+                    }
+                    let line = serde_json::to_string(&event).unwrap_or_default();
+                    if out_tx.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }))
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, String> {
+    serde_json::from_value(params).map_err(|e| format!("Invalid params: {}", e))
+}
+
+fn parse_params_or_default<T: for<'de> Deserialize<'de> + Default>(params: Value) -> Result<T, String> {
+    if params.is_null() {
+        Ok(T::default())
+    } else {
+        parse_params(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct NoEvents;
+
+    impl EventSink for NoEvents {
+        fn emit_event(&self, _event: &str, _payload: Value) {}
+    }
+
+    fn request(token: Option<&str>) -> Request {
+        Request {
+            id: Value::Null,
+            method: "get_state".to_string(),
+            params: Value::Null,
+            token: token.map(str::to_string),
+        }
+    }
+
+    /// Send `input` on a fresh connection and collect the response lines
+    async fn exchange(input: &str, token: Option<&str>) -> Vec<Value> {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let task = tokio::spawn(handle_connection(server, NoEvents, token.map(Into::into)));
+        let (mut reader, mut writer) = tokio::io::split(client);
+        writer.write_all(input.as_bytes()).await.unwrap();
+        writer.shutdown().await.unwrap();
+        let mut output = String::new();
+        reader.read_to_string(&mut output).await.unwrap();
+        task.await.unwrap();
+        output.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn http_lines_are_recognized() {
+        assert!(looks_like_http("GET / HTTP/1.1"));
+        assert!(looks_like_http("  post /start HTTP/1.1"));
+        assert!(looks_like_http("Host: 127.0.0.1:47400"));
+        assert!(!looks_like_http(r#"{"id": 1, "method": "get"}"#));
+        assert!(!looks_like_http("GETTER"));
+    }
+
+    #[test]
+    fn tokens_must_match_exactly() {
+        assert!(authorized(None, &request(None)));
+        assert!(authorized(Some("abc123"), &request(Some("abc123"))));
+        assert!(!authorized(Some("abc123"), &request(None)));
+        assert!(!authorized(Some("abc123"), &request(Some("abc124"))));
+        assert!(!authorized(Some("abc123"), &request(Some("abc1234"))));
+    }
+
+    #[test]
+    fn params_may_be_left_out_for_optional_ones() {
+        let abort: AbortParams = parse_params_or_default(Value::Null).unwrap();
+        assert!(!abort.undo);
+        assert!(parse_params::<LoadFileParams>(Value::Null).is_err());
+        assert!(parse_params::<LoadFileParams>(serde_json::json!({ "file": "x" }))
+            .unwrap_err()
+            .starts_with("Invalid params"));
+    }
+
+    #[tokio::test]
+    async fn each_request_gets_one_response_with_its_id() {
+        let responses = exchange(
+            "{\"id\": 1, \"method\": \"get_queue\"}\n\n{\"id\": \"a\", \"method\": \"nope\"}\nnot json\n",
+            None,
+        )
+        .await;
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["ok"], true);
+        assert!(responses[0]["result"].is_array());
+        assert_eq!(responses[1]["id"], "a");
+        assert_eq!(responses[1]["error"], "Unknown method: nope");
+        assert_eq!(responses[2]["id"], Value::Null);
+        assert!(responses[2]["error"].as_str().unwrap().starts_with("Invalid request"));
+    }
+
+    #[tokio::test]
+    async fn requests_without_the_token_are_refused() {
+        let responses = exchange(
+            "{\"id\": 1, \"method\": \"get_queue\"}\n{\"id\": 2, \"method\": \"get_queue\", \"token\": \"secret\"}\n",
+            Some("secret"),
+        )
+        .await;
+        assert_eq!(responses[0]["error"], "Invalid token");
+        assert_eq!(responses[1]["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn http_requests_are_closed_without_an_answer() {
+        let responses = exchange("GET / HTTP/1.1\r\n{\"id\": 1, \"method\": \"get_queue\"}\n", None).await;
+        assert!(responses.is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::config::{HotkeyAction, Hotkeys};
+use crate::{
+    abort_typing, adjust_speed, engine, handle_tray_pause_resume, handle_tray_start_stop,
+    handle_tray_type_clipboard, settings,
+};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+/// Settings key for the persisted bindings
+const SETTINGS_KEY: &str = "hotkeys";
+
+/// Currently registered shortcuts and the action each one triggers
+static REGISTERED: Lazy<Mutex<Vec<(Shortcut, HotkeyAction)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Current bindings as configured by the user
+static CURRENT: Lazy<Mutex<Hotkeys>> = Lazy::new(|| Mutex::new(Hotkeys::default()));
+
+/// Error from the last attempt to register the bindings
+static LAST_ERROR: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn get() -> Hotkeys {
+    CURRENT.lock().clone()
+}
+
+/// Why the current bindings could not be registered, if they could not
+pub fn last_error() -> Option<String> {
+    LAST_ERROR.lock().clone()
+}
+
+/// Parse every binding, rejecting invalid shortcuts and duplicates
+pub fn parse(hotkeys: &Hotkeys) -> Result<Vec<(Shortcut, HotkeyAction)>, String> {
+    let mut parsed: Vec<(Shortcut, HotkeyAction)> = Vec::new();
+    let mut seen: HashMap<Shortcut, HotkeyAction> = HashMap::new();
+
+    for (action, text) in hotkeys.bindings() {
+        let shortcut = Shortcut::from_str(text)
+            .map_err(|e| format!("Invalid shortcut '{}' for {}: {}", text, action.label(), e))?;
+
+        if let Some(other) = seen.insert(shortcut, action) {
+            return Err(format!(
+                "'{}' is bound to both {} and {}",
+                text,
+                other.label(),
+                action.label()
+            ));
+        }
+        parsed.push((shortcut, action));
+    }
+
+    Ok(parsed)
+}
+
+/// Replace the registered shortcuts with `hotkeys`. On failure the previous
+/// bindings are restored and the error is returned.
+pub fn apply(app: &AppHandle, hotkeys: &Hotkeys) -> Result<(), String> {
+    let result = register(app, hotkeys);
+    *LAST_ERROR.lock() = result.as_ref().err().cloned();
+    result
+}
+
+fn register(app: &AppHandle, hotkeys: &Hotkeys) -> Result<(), String> {
+    let parsed = parse(hotkeys)?;
+    let mut registered = REGISTERED.lock();
+    let global = app.global_shortcut();
+
+    for (shortcut, _) in registered.iter() {
+        let _ = global.unregister(*shortcut);
+    }
+
+    for (i, (shortcut, action)) in parsed.iter().enumerate() {
+        if let Err(e) = global.register(*shortcut) {
+            // Roll back to the previous bindings
+            for (done, _) in &parsed[..i] {
+                let _ = global.unregister(*done);
+            }
+            for (previous, _) in registered.iter() {
+                let _ = global.register(*previous);
+            }
+            return Err(format!(
+                "Could not register '{}' for {} (is it used by another application?): {}",
+                shortcut.into_string(),
+                action.label(),
+                e
+            ));
+        }
+    }
+
+    *registered = parsed;
+    *CURRENT.lock() = hotkeys.clone();
+    Ok(())
+}
+
+/// Apply and persist new bindings
+pub fn set(app: &AppHandle, hotkeys: Hotkeys) -> Result<(), String> {
+    apply(app, &hotkeys)?;
+    settings::save(app, SETTINGS_KEY, &hotkeys)
+}
+
+/// Register the persisted bindings (or the defaults) at startup
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let hotkeys = settings::load(app, SETTINGS_KEY).unwrap_or_default();
+    *CURRENT.lock() = hotkeys;
+    apply(app, &get())
+}
+
+/// Run the action bound to `shortcut`
+pub fn handle(app: &AppHandle, shortcut: &Shortcut) {
+    let action = REGISTERED
+        .lock()
+        .iter()
+        .find(|(s, _)| s == shortcut)
+        .map(|(_, action)| *action);
+
+    match action {
+        Some(HotkeyAction::StartStop) => handle_tray_start_stop(app),
+        Some(HotkeyAction::PauseResume) => handle_tray_pause_resume(app),
+        Some(HotkeyAction::SkipParagraph) => engine().skip_paragraph(),
+        Some(HotkeyAction::SpeedUp) => {
+            adjust_speed(app, 1);
+        }
+        Some(HotkeyAction::SpeedDown) => {
+            adjust_speed(app, -1);
+        }
+        Some(HotkeyAction::Abort) => abort_typing(app, get().undo_on_abort),
+        Some(HotkeyAction::TypeClipboard) => handle_tray_type_clipboard(app),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings_parse() {
+        let parsed = parse(&Hotkeys::default()).unwrap();
+        assert_eq!(parsed.len(), 7);
+        assert_eq!(parsed[0], (Shortcut::from_str("Ctrl+Alt+S").unwrap(), HotkeyAction::StartStop));
+    }
+
+    #[test]
+    fn unbound_actions_are_skipped() {
+        let hotkeys = Hotkeys {
+            start_stop: None,
+            pause_resume: Some("  ".to_string()),
+            ..Hotkeys::default()
+        };
+        let parsed = parse(&hotkeys).unwrap();
+        assert_eq!(parsed.len(), 5);
+        assert!(parsed.iter().all(|(_, action)| !matches!(action, HotkeyAction::StartStop | HotkeyAction::PauseResume)));
+    }
+
+    #[test]
+    fn invalid_shortcuts_name_the_action() {
+        let hotkeys = Hotkeys {
+            abort: Some("Ctrl+Nope".to_string()),
+            ..Hotkeys::default()
+        };
+        let error = parse(&hotkeys).unwrap_err();
+        assert!(error.starts_with("Invalid shortcut 'Ctrl+Nope' for abort"), "{}", error);
+    }
+
+    #[test]
+    fn the_same_shortcut_spelled_differently_conflicts() {
+        let hotkeys = Hotkeys {
+            speed_up: Some("Alt+Control+S".to_string()),
+            ..Hotkeys::default()
+        };
+        assert_eq!(
+            parse(&hotkeys).unwrap_err(),
+            "'Alt+Control+S' is bound to both start/stop and speed up"
+        );
+    }
+}
@@ -1,13 +1,34 @@
 pub mod config;
+pub mod content;
+pub mod control;
+#[cfg(feature = "app")]
+pub mod hotkeys;
+#[cfg(feature = "app")]
+pub mod settings;
 pub mod typer;
+pub mod watch;
 
+use content::formats::{self, Extracted, SourceFormat, TextEncoding};
+use content::Document;
+use content::normalize::{self, Normalization, Normalized};
 use once_cell::sync::Lazy;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tauri::{AppHandle, Manager};
+#[cfg(feature = "app")]
+use tauri::{async_runtime::spawn, AppHandle, Manager};
+#[cfg(not(feature = "app"))]
+use tokio::spawn;
+use typer::calibrate::{self, Calibration, RecordedKey};
+use typer::events::EventSink;
+use typer::ngrams;
+use typer::queue::QueueItem;
+use typer::stats::{self, LogFormat, SessionReport};
+use typer::trigger::StartTrigger;
 use typer::TypingEngine;
 
 // Re-export types for use in main.rs
-pub use config::{Config, FileInfo, TypingStatus};
+pub use config::{Config, FileInfo, Hotkeys, TypingStatus};
+pub use typer::TypingError;
 
 /// Global typing engine instance
 static ENGINE: Lazy<Arc<TypingEngine>> = Lazy::new(|| Arc::new(TypingEngine::new()));
@@ -17,21 +38,389 @@ pub fn engine() -> &'static Arc<TypingEngine> {
     &ENGINE
 }
 
+// ============================================================================
+// Engine Operations (shared by Tauri commands and the control API)
+// ============================================================================
+
+/// Read a file as (name, extracted text)
+fn read_file(path: &str) -> Result<(String, Extracted), String> {
+    let path = std::path::Path::new(path);
+    let extracted = formats::read_file(path, engine().get_config().max_file_mb)?;
+
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    Ok((name, extracted))
+}
+
+/// Characters of the content sent to the UI as a preview
+const PREVIEW_CHARS: usize = 100_000;
+
+fn file_info(document: &Document, format: SourceFormat, encoding: Option<TextEncoding>) -> FileInfo {
+    let text = &document.text;
+    let preview_end = text.char_indices().nth(PREVIEW_CHARS).map_or(text.len(), |(i, _)| i);
+    FileInfo {
+        name: document.name.clone(),
+        content: text[..preview_end].to_string(),
+        truncated: preview_end < text.len(),
+        char_count: text.chars().count() as u32,
+        code_language: document.code_language,
+        format,
+        encoding,
+        normalized: document.normalized,
+        variables: document.variables.clone(),
+    }
+}
+
+/// Load a file as the content, watching it for changes if the config asks
+/// for it
+pub fn load_file<S: EventSink + Clone>(events: S, path: &str) -> Result<FileInfo, String> {
+    let (name, extracted) = read_file(path)?;
+    let config = engine().get_config();
+    let document = content::prepare(name, extracted.text, &config);
+
+    let info = file_info(&document, extracted.format, extracted.encoding);
+    let text = document.text.clone();
+    engine().set_content(document)?;
+    if config.watch_file {
+        watch::start(std::path::Path::new(path), text, events)?;
+    } else {
+        watch::stop();
+    }
+
+    Ok(info)
+}
+
+/// Replace the content with `document`, ending any file watch
+pub fn set_content(document: Document) -> Result<(), String> {
+    engine().set_content(document)?;
+    watch::stop();
+    Ok(())
+}
+
+/// Fail if `text` is over the active config's size limit
+fn check_text_size(text: &str) -> Result<(), String> {
+    formats::check_size(text.len() as u64, engine().get_config().max_file_mb)
+}
+
+/// Prepare `text` and make it the content, unless it is over the size limit
+pub fn load_text(name: String, text: String) -> Result<(), String> {
+    check_text_size(&text)?;
+    set_content(content::prepare(name, text, &engine().get_config()))
+}
+
+/// Name clipboard content is loaded under
+pub const CLIPBOARD_NAME: &str = "Clipboard";
+
+/// Replace the content with the clipboard text and start typing it with the
+/// active profile
+pub fn type_clipboard<S: EventSink + Clone>(events: S) -> Result<FileInfo, String> {
+    if engine().get_status().is_active() {
+        return Err("Already typing".to_string());
+    }
+    let text = formats::read_clipboard()?;
+    check_text_size(&text)?;
+    let document = content::prepare(CLIPBOARD_NAME.to_string(), text, &engine().get_config());
+
+    let info = file_info(&document, SourceFormat::Text, None);
+    set_content(document)?;
+    engine().emit(
+        &events,
+        "content-loaded",
+        serde_json::to_value(&info).unwrap_or_default(),
+    );
+    emit_queue(&events);
+
+    start_typing(events, None)?;
+    Ok(info)
+}
+
+/// Show what normalizing `content` would change, with the active config's
+/// options unless others are given
+pub fn preview_normalization(content: &str, options: Option<Normalization>) -> Normalized {
+    let options = options.unwrap_or_else(|| engine().get_config().normalization);
+    normalize::normalize(content, &options)
+}
+
+/// Where queued content comes from
+pub enum QueueSource {
+    Text { content: String, name: String },
+    File { path: String },
+}
+
+/// Replace the active config. A configured n-gram file must be readable;
+/// tables are reloaded so edits to it take effect.
+pub fn set_config(config: Config) -> Result<(), String> {
+    if let Some(path) = &config.ngram_file {
+        ngrams::validate_file(path)?;
+    }
+    config.editor_shortcuts.parse()?;
+    if !config.watch_file {
+        watch::stop();
+    }
+    ngrams::reload();
+    engine().set_config(config);
+    Ok(())
+}
+
+/// Append a document to the queue
+pub fn enqueue(
+    events: &impl EventSink,
+    source: QueueSource,
+    profile: Option<String>,
+    config: Option<Config>,
+    trigger: Option<StartTrigger>,
+) -> Result<QueueItem, String> {
+    ensure_queue_editable()?;
+    let (name, content) = match source {
+        QueueSource::Text { content, name } => {
+            check_text_size(&content)?;
+            (name, content)
+        }
+        QueueSource::File { path } => {
+            let (name, extracted) = read_file(&path)?;
+            (name, extracted.text)
+        }
+    };
+    // Markdown settings come from the item's own config, if it has one
+    let document = match &config {
+        Some(config) => content::prepare(name, content, config),
+        None => content::prepare(name, content, &engine().get_config()),
+    };
+    let item = engine().enqueue(document, profile, config, trigger)?;
+    // A reload would replace the whole queue
+    watch::stop();
+    emit_queue(events);
+    Ok(item)
+}
+
+pub fn remove_queue_item(events: &impl EventSink, id: u64) -> Result<(), String> {
+    ensure_queue_editable()?;
+    engine().remove_queue_item(id)?;
+    emit_queue(events);
+    Ok(())
+}
+
+pub fn move_queue_item(events: &impl EventSink, id: u64, index: usize) -> Result<(), String> {
+    ensure_queue_editable()?;
+    engine().move_queue_item(id, index)?;
+    emit_queue(events);
+    Ok(())
+}
+
+pub fn clear_queue(events: &impl EventSink) -> Result<(), String> {
+    ensure_queue_editable()?;
+    watch::stop();
+    engine().clear_queue();
+    emit_queue(events);
+    Ok(())
+}
+
+/// Start the queue item waiting for the hotkey
+pub fn continue_queue() -> Result<(), String> {
+    if engine().get_status() != TypingStatus::Waiting {
+        return Err("Not waiting for the next item".to_string());
+    }
+    engine().trigger();
+    Ok(())
+}
+
+fn ensure_queue_editable() -> Result<(), String> {
+    if engine().get_status().is_active() {
+        return Err("Cannot change the queue while typing".to_string());
+    }
+    Ok(())
+}
+
+fn emit_queue(events: &impl EventSink) {
+    engine().emit(
+        events,
+        "queue-changed",
+        serde_json::to_value(engine().get_queue()).unwrap_or_default(),
+    );
+}
+
+/// Set the values template variables are filled in with
+pub fn set_template_variables(variables: HashMap<String, String>) {
+    engine().set_variables(variables);
+}
+
+/// Template variables of queued documents that still need a value
+pub fn get_missing_variables() -> Vec<String> {
+    engine().missing_variables()
+}
+
+fn ensure_variables() -> Result<(), String> {
+    let missing = engine().missing_variables();
+    if !missing.is_empty() {
+        return Err(format!("Missing template variables: {}", missing.join(", ")));
+    }
+    Ok(())
+}
+
+/// Start typing, first adding `variables` to the template values
+pub fn start_typing<S: EventSink + Clone>(
+    events: S,
+    variables: Option<HashMap<String, String>>,
+) -> Result<(), String> {
+    let status = engine().get_status();
+
+    match status {
+        TypingStatus::Ready | TypingStatus::Done | TypingStatus::Idle => {
+            if let Some(variables) = variables {
+                let mut values = engine().get_variables();
+                values.extend(variables);
+                engine().set_variables(values);
+            }
+            ensure_variables()?;
+            // Start typing
+            let eng = engine().clone();
+            spawn(async move {
+                if let Err(e) = eng.clone().run(events.clone()).await {
+                    eprintln!("Typing error: {}", e);
+                    eng.emit(
+                        &events,
+                        "typing-error",
+                        serde_json::json!({ "message": e.to_string() }),
+                    );
+                }
+            });
+            Ok(())
+        }
+        TypingStatus::Typing | TypingStatus::Countdown => Err("Already typing".to_string()),
+        TypingStatus::Waiting => continue_queue(),
+        TypingStatus::Paused | TypingStatus::FocusLost | TypingStatus::OnBreak => {
+            // Resume instead
+            engine().resume();
+            engine().set_status(TypingStatus::Typing, &events);
+            Ok(())
+        }
+        TypingStatus::Error => Err("Cannot start while in error state".to_string()),
+    }
+}
+
+pub fn stop_typing(events: &impl EventSink) {
+    engine().stop();
+    engine().set_status(TypingStatus::Ready, events);
+}
+
+/// Emergency stop; with `undo` everything typed in this run is backspaced.
+/// The run sets Ready itself once the undo is done.
+pub fn abort_typing(events: &impl EventSink, undo: bool) {
+    let active = engine().get_status().is_active();
+    engine().abort(undo);
+    if !(undo && active) {
+        engine().set_status(TypingStatus::Ready, events);
+    }
+}
+
+pub fn pause_typing(events: &impl EventSink) {
+    engine().pause();
+    engine().set_status(TypingStatus::Paused, events);
+}
+
+pub fn resume_typing(events: &impl EventSink) {
+    engine().resume();
+    engine().set_status(TypingStatus::Typing, events);
+}
+
+/// Nudge the speed by `steps` increments, returning the new WPM. Takes
+/// effect immediately in a running session.
+pub fn adjust_speed(events: &impl EventSink, steps: i32) -> u32 {
+    let wpm = engine().adjust_speed(steps);
+    engine().emit(
+        events,
+        "config-changed",
+        serde_json::to_value(engine().get_config()).unwrap_or_default(),
+    );
+    wpm
+}
+
+pub fn get_state() -> serde_json::Value {
+    let progress = engine().get_progress();
+    let status = engine().get_status();
+    let file_name = engine().get_file_name();
+
+    serde_json::json!({
+        "status": status,
+        "current_char": progress.current,
+        "total_chars": progress.total,
+        "file_name": file_name,
+        "typed_chars": engine().typed_chars(),
+        "queue_item": progress.item,
+        "queue_length": progress.items,
+        "watched_file": watch::watched_path(),
+    })
+}
+
+/// Languages with a bundled n-gram timing table
+pub fn get_languages() -> Vec<&'static str> {
+    ngrams::languages()
+}
+
+/// Report of the last finished run
+pub fn get_session_report() -> Option<SessionReport> {
+    engine().session_report()
+}
+
+/// Write the last run's keystroke log to `path`, returning the number of
+/// records written
+pub fn export_keystroke_log(path: &str, format: LogFormat) -> Result<usize, String> {
+    let log = engine().keystroke_log();
+    if log.is_empty() {
+        return Err("No keystroke log recorded (enable keystroke logging before the run)".to_string());
+    }
+    let data = stats::format_log(&log, format)?;
+    std::fs::write(path, data).map_err(|e| format!("Failed to write log: {}", e))?;
+    Ok(log.len())
+}
+
+/// Fit a profile to recorded keystrokes, starting from the active config
+pub fn calibrate_keys(keys: &[RecordedKey], reference: Option<&str>) -> Result<Calibration, String> {
+    calibrate::calibrate(keys, reference, &engine().get_config())
+}
+
+/// Fit a profile to a recording file (JSON array or JSON Lines, e.g. an
+/// exported keystroke log)
+pub fn calibrate_file(path: &str, reference: Option<&str>) -> Result<Calibration, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read recording: {}", e))?;
+    let keys = calibrate::parse_recording(&text)?;
+    calibrate_keys(&keys, reference)
+}
+
 // ============================================================================
 // Tray Menu Handlers
 // ============================================================================
 
+#[cfg(feature = "app")]
 pub fn handle_tray_start_stop(app: &AppHandle) {
     let status = engine().get_status();
     match status {
-        TypingStatus::Typing | TypingStatus::Countdown | TypingStatus::Paused => {
+        TypingStatus::Typing
+        | TypingStatus::Countdown
+        | TypingStatus::Paused
+        | TypingStatus::FocusLost
+        | TypingStatus::OnBreak => {
             engine().stop();
             engine().set_status(TypingStatus::Ready, app);
         }
+        TypingStatus::Waiting => {
+            let _ = continue_queue();
+        }
         TypingStatus::Ready | TypingStatus::Done => {
+            // The main window asks for missing template variables
+            let missing = engine().missing_variables();
+            if !missing.is_empty() {
+                show_main_window(app);
+                engine().emit(app, "variables-required", serde_json::json!({ "names": missing }));
+                return;
+            }
             let app = app.clone();
             let engine = engine().clone();
-            tokio::spawn(async move {
+            spawn(async move {
                 if let Err(e) = engine.run(app.clone()).await {
                     eprintln!("Typing error: {}", e);
                 }
@@ -41,6 +430,15 @@ pub fn handle_tray_start_stop(app: &AppHandle) {
     }
 }
 
+#[cfg(feature = "app")]
+pub fn handle_tray_type_clipboard(app: &AppHandle) {
+    if let Err(e) = type_clipboard(app.clone()) {
+        eprintln!("Typing error: {}", e);
+        engine().emit(app, "typing-error", serde_json::json!({ "message": e }));
+    }
+}
+
+#[cfg(feature = "app")]
 pub fn handle_tray_pause_resume(app: &AppHandle) {
     let status = engine().get_status();
     match status {
@@ -48,7 +446,7 @@ pub fn handle_tray_pause_resume(app: &AppHandle) {
             engine().pause();
             engine().set_status(TypingStatus::Paused, app);
         }
-        TypingStatus::Paused => {
+        TypingStatus::Paused | TypingStatus::FocusLost | TypingStatus::OnBreak => {
             engine().resume();
             engine().set_status(TypingStatus::Typing, app);
         }
@@ -56,6 +454,7 @@ pub fn handle_tray_pause_resume(app: &AppHandle) {
     }
 }
 
+#[cfg(feature = "app")]
 pub fn toggle_widget(app: &AppHandle) {
     if let Some(widget) = app.get_webview_window("widget") {
         if widget.is_visible().unwrap_or(false) {
@@ -67,6 +466,7 @@ pub fn toggle_widget(app: &AppHandle) {
     }
 }
 
+#[cfg(feature = "app")]
 pub fn show_main_window(app: &AppHandle) {
     if let Some(main) = app.get_webview_window("main") {
         let _ = main.show();
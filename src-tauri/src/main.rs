@@ -1,8 +1,13 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use ghostkeys_lib::content::normalize::{Normalization, Normalized};
+use ghostkeys_lib::typer::calibrate::{Calibration, RecordedKey};
+use ghostkeys_lib::typer::queue::QueueItem;
+use ghostkeys_lib::typer::stats::{LogFormat, SessionReport};
+use ghostkeys_lib::typer::trigger::StartTrigger;
 use ghostkeys_lib::{
-    engine, handle_tray_pause_resume, handle_tray_start_stop, show_main_window, toggle_widget,
-    Config, FileInfo, TypingStatus,
+    control, engine, handle_tray_pause_resume, handle_tray_start_stop, handle_tray_type_clipboard,
+    hotkeys, show_main_window, toggle_widget, Config, FileInfo, Hotkeys, QueueSource,
 };
 use tauri::{
     image::Image,
@@ -10,7 +15,8 @@ use tauri::{
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     AppHandle, Emitter,
 };
-use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+use std::collections::HashMap;
+use tauri_plugin_global_shortcut::ShortcutState;
 
 // ============================================================================
 // Tauri Commands
@@ -22,101 +28,178 @@ fn get_config() -> Config {
 }
 
 #[tauri::command]
-fn set_config(config: Config) {
-    engine().set_config(config);
+fn set_config(config: Config) -> Result<(), String> {
+    ghostkeys_lib::set_config(config)
 }
 
 #[tauri::command]
-fn set_file_content(content: String, file_name: String) {
-    engine().set_content(content, file_name);
+fn get_languages() -> Vec<&'static str> {
+    ghostkeys_lib::get_languages()
 }
 
 #[tauri::command]
-fn load_file(path: String) -> Result<FileInfo, String> {
-    let content = std::fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+fn speed_up(app: AppHandle) -> u32 {
+    ghostkeys_lib::adjust_speed(&app, 1)
+}
 
-    let name = std::path::Path::new(&path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+#[tauri::command]
+fn speed_down(app: AppHandle) -> u32 {
+    ghostkeys_lib::adjust_speed(&app, -1)
+}
 
-    let char_count = content.len() as u32;
+#[tauri::command]
+fn set_file_content(content: String, file_name: String) -> Result<(), String> {
+    ghostkeys_lib::load_text(file_name, content)
+}
 
-    engine().set_content(content.clone(), name.clone());
+#[tauri::command]
+fn load_file(app: AppHandle, path: String) -> Result<FileInfo, String> {
+    ghostkeys_lib::load_file(app, &path)
+}
 
-    Ok(FileInfo {
-        name,
-        content,
-        char_count,
-    })
+#[tauri::command]
+async fn type_clipboard(app: AppHandle) -> Result<FileInfo, String> {
+    ghostkeys_lib::type_clipboard(app)
 }
 
 #[tauri::command]
-async fn start_typing(app: AppHandle) -> Result<(), String> {
-    let status = engine().get_status();
-
-    match status {
-        TypingStatus::Ready
-        | TypingStatus::Done
-        | TypingStatus::Idle => {
-            // Start typing
-            let eng = engine().clone();
-            tokio::spawn(async move {
-                if let Err(e) = eng.run(app.clone()).await {
-                    eprintln!("Typing error: {}", e);
-                    let _ = app.emit("typing-error", serde_json::json!({ "message": e }));
-                }
-            });
-            Ok(())
-        }
-        TypingStatus::Typing
-        | TypingStatus::Countdown => {
-            Err("Already typing".to_string())
-        }
-        TypingStatus::Paused => {
-            // Resume instead
-            engine().resume();
-            engine().set_status(TypingStatus::Typing, &app);
-            Ok(())
-        }
-        TypingStatus::Error => {
-            Err("Cannot start while in error state".to_string())
-        }
-    }
+fn preview_normalization(content: String, options: Option<Normalization>) -> Normalized {
+    ghostkeys_lib::preview_normalization(&content, options)
+}
+
+#[tauri::command]
+fn enqueue_content(
+    app: AppHandle,
+    content: String,
+    name: String,
+    profile: Option<String>,
+    config: Option<Config>,
+    trigger: Option<StartTrigger>,
+) -> Result<QueueItem, String> {
+    ghostkeys_lib::enqueue(
+        &app,
+        QueueSource::Text { content, name },
+        profile,
+        config,
+        trigger,
+    )
+}
+
+#[tauri::command]
+fn enqueue_file(
+    app: AppHandle,
+    path: String,
+    profile: Option<String>,
+    config: Option<Config>,
+    trigger: Option<StartTrigger>,
+) -> Result<QueueItem, String> {
+    ghostkeys_lib::enqueue(
+        &app,
+        QueueSource::File { path },
+        profile,
+        config,
+        trigger,
+    )
+}
+
+#[tauri::command]
+fn get_queue() -> Vec<QueueItem> {
+    engine().get_queue()
+}
+
+#[tauri::command]
+fn remove_queue_item(app: AppHandle, id: u64) -> Result<(), String> {
+    ghostkeys_lib::remove_queue_item(&app, id)
+}
+
+#[tauri::command]
+fn move_queue_item(app: AppHandle, id: u64, index: usize) -> Result<(), String> {
+    ghostkeys_lib::move_queue_item(&app, id, index)
+}
+
+#[tauri::command]
+fn clear_queue(app: AppHandle) -> Result<(), String> {
+    ghostkeys_lib::clear_queue(&app)
+}
+
+#[tauri::command]
+fn continue_queue() -> Result<(), String> {
+    ghostkeys_lib::continue_queue()
+}
+
+#[tauri::command]
+async fn start_typing(app: AppHandle, variables: Option<HashMap<String, String>>) -> Result<(), String> {
+    ghostkeys_lib::start_typing(app, variables)
+}
+
+#[tauri::command]
+fn get_missing_variables() -> Vec<String> {
+    ghostkeys_lib::get_missing_variables()
+}
+
+#[tauri::command]
+fn set_template_variables(variables: HashMap<String, String>) {
+    ghostkeys_lib::set_template_variables(variables);
 }
 
 #[tauri::command]
 fn stop_typing(app: AppHandle) {
-    engine().stop();
-    engine().set_status(TypingStatus::Ready, &app);
+    ghostkeys_lib::stop_typing(&app);
+}
+
+#[tauri::command]
+fn abort_typing(app: AppHandle, undo: bool) {
+    ghostkeys_lib::abort_typing(&app, undo);
 }
 
 #[tauri::command]
 fn pause_typing(app: AppHandle) {
-    engine().pause();
-    engine().set_status(TypingStatus::Paused, &app);
+    ghostkeys_lib::pause_typing(&app);
 }
 
 #[tauri::command]
 fn resume_typing(app: AppHandle) {
-    engine().resume();
-    engine().set_status(TypingStatus::Typing, &app);
+    ghostkeys_lib::resume_typing(&app);
 }
 
 #[tauri::command]
 fn get_state() -> serde_json::Value {
-    let progress = engine().get_progress();
-    let status = engine().get_status();
-    let file_name = engine().get_file_name();
+    ghostkeys_lib::get_state()
+}
 
-    serde_json::json!({
-        "status": status,
-        "current_char": progress.current,
-        "total_chars": progress.total,
-        "file_name": file_name,
-    })
+#[tauri::command]
+fn get_session_report() -> Option<SessionReport> {
+    ghostkeys_lib::get_session_report()
+}
+
+#[tauri::command]
+fn export_keystroke_log(path: String, format: LogFormat) -> Result<usize, String> {
+    ghostkeys_lib::export_keystroke_log(&path, format)
+}
+
+#[tauri::command]
+fn calibrate_from_keys(keys: Vec<RecordedKey>, reference: Option<String>) -> Result<Calibration, String> {
+    ghostkeys_lib::calibrate_keys(&keys, reference.as_deref())
+}
+
+#[tauri::command]
+fn calibrate_from_file(path: String, reference: Option<String>) -> Result<Calibration, String> {
+    ghostkeys_lib::calibrate_file(&path, reference.as_deref())
+}
+
+#[tauri::command]
+fn get_hotkeys() -> Hotkeys {
+    hotkeys::get()
+}
+
+#[tauri::command]
+fn get_hotkey_error() -> Option<String> {
+    hotkeys::last_error()
+}
+
+#[tauri::command]
+fn set_hotkeys(app: AppHandle, hotkeys: Hotkeys) -> Result<(), String> {
+    hotkeys::set(&app, hotkeys)
 }
 
 // ============================================================================
@@ -124,44 +207,59 @@ fn get_state() -> serde_json::Value {
 // ============================================================================
 
 fn main() {
-    // Capture app handle for use in global shortcut handler
-    let app_handle_for_shortcut: std::sync::Arc<std::sync::Mutex<Option<AppHandle>>> =
-        std::sync::Arc::new(std::sync::Mutex::new(None));
-    let app_handle_clone = app_handle_for_shortcut.clone();
-
+    ghostkeys_lib::content::pdf::run_helper_if_requested();
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
-                .with_handler(move |_app, _shortcut, event| {
+                .with_handler(|app, shortcut, event| {
                     if event.state() == ShortcutState::Pressed {
-                        if let Some(ref handle) = *app_handle_clone.lock().unwrap() {
-                            handle_tray_start_stop(handle);
-                        }
+                        hotkeys::handle(app, shortcut);
                     }
                 })
                 .build(),
         )
         .invoke_handler(tauri::generate_handler![
             load_file,
+            type_clipboard,
+            preview_normalization,
             start_typing,
+            get_missing_variables,
+            set_template_variables,
             stop_typing,
+            abort_typing,
             pause_typing,
             resume_typing,
             get_config,
             set_config,
+            get_languages,
+            speed_up,
+            speed_down,
             get_state,
+            get_session_report,
+            export_keystroke_log,
+            calibrate_from_keys,
+            calibrate_from_file,
             set_file_content,
+            enqueue_content,
+            enqueue_file,
+            get_queue,
+            remove_queue_item,
+            move_queue_item,
+            clear_queue,
+            continue_queue,
+            get_hotkeys,
+            get_hotkey_error,
+            set_hotkeys,
         ])
-        .setup(move |app| {
-            // Store app handle for global shortcut handler
-            *app_handle_for_shortcut.lock().unwrap() = Some(app.handle().clone());
-
+        .setup(|app| {
             // Build tray menu
             let start_stop = MenuItem::with_id(app, "start_stop", "Start/Stop", true, None::<&str>)?;
             let pause_resume =
                 MenuItem::with_id(app, "pause_resume", "Pause/Resume", true, None::<&str>)?;
+            let type_clipboard_item =
+                MenuItem::with_id(app, "type_clipboard", "Type Clipboard", true, None::<&str>)?;
             let show_main = MenuItem::with_id(app, "show_main", "Show Window", true, None::<&str>)?;
             let toggle_widget_item =
                 MenuItem::with_id(app, "toggle_widget", "Toggle Widget", true, None::<&str>)?;
@@ -172,6 +270,7 @@ fn main() {
                 &[
                     &start_stop,
                     &pause_resume,
+                    &type_clipboard_item,
                     &show_main,
                     &toggle_widget_item,
                     &quit,
@@ -191,6 +290,7 @@ fn main() {
                 .on_menu_event(|app, event| match event.id.as_ref() {
                     "start_stop" => handle_tray_start_stop(app),
                     "pause_resume" => handle_tray_pause_resume(app),
+                    "type_clipboard" => handle_tray_type_clipboard(app),
                     "show_main" => show_main_window(app),
                     "toggle_widget" => toggle_widget(app),
                     "quit" => {
@@ -210,10 +310,18 @@ fn main() {
                 })
                 .build(app)?;
 
-            // Register global shortcut (Ctrl+Alt+S)
-            let shortcut = Shortcut::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyS);
-            if let Err(e) = app.global_shortcut().register(shortcut) {
-                eprintln!("Failed to register global shortcut: {}", e);
+            // Start the local control API
+            let control_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = control::serve(control_handle).await {
+                    eprintln!("Control API unavailable: {}", e);
+                }
+            });
+
+            // Register global shortcuts
+            if let Err(e) = hotkeys::init(app.handle()) {
+                eprintln!("Failed to register global shortcuts: {}", e);
+                let _ = app.emit("hotkey-error", serde_json::json!({ "message": e }));
             }
 
             Ok(())
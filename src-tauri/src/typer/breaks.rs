@@ -0,0 +1,69 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// When to take breaks during long sessions
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BreakSchedule {
+    pub enabled: bool,
+    /// Minutes of typing between breaks
+    pub every_minutes: f64,
+    /// Break length is drawn uniformly from this range (seconds)
+    pub min_secs: u64,
+    pub max_secs: u64,
+    /// Once a break is due, wait for the end of a paragraph (for at most
+    /// half the interval again)
+    pub at_paragraph: bool,
+    /// Show the "on break" status; otherwise a break looks like typing
+    pub show_status: bool,
+}
+
+impl Default for BreakSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            every_minutes: 25.0,
+            min_secs: 60,
+            max_secs: 300,
+            at_paragraph: true,
+            show_status: true,
+        }
+    }
+}
+
+/// Typing time since the last break
+#[derive(Debug, Default)]
+pub struct BreakTimer {
+    typed: Duration,
+}
+
+impl BreakTimer {
+    pub fn add(&mut self, typed: Duration) {
+        self.typed += typed;
+    }
+
+    pub fn reset(&mut self) {
+        self.typed = Duration::ZERO;
+    }
+
+    /// Length of the break to take now, if one is due. `at_paragraph` tells
+    /// whether typing is at a paragraph boundary.
+    pub fn due(&self, schedule: &BreakSchedule, at_paragraph: bool) -> Option<Duration> {
+        if !schedule.enabled || schedule.every_minutes <= 0.0 {
+            return None;
+        }
+        // Non-finite or absurdly long intervals never come due
+        let every = Duration::try_from_secs_f64(schedule.every_minutes * 60.0).ok()?;
+        if self.typed < every {
+            return None;
+        }
+        if schedule.at_paragraph && !at_paragraph && self.typed < every.saturating_add(every / 2) {
+            return None;
+        }
+
+        let (min, max) = (schedule.min_secs, schedule.max_secs.max(schedule.min_secs));
+        let secs = rand::thread_rng().gen_range(min..=max);
+        Some(Duration::from_secs(secs))
+    }
+}
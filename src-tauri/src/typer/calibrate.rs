@@ -0,0 +1,525 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::plan::Action;
+use super::{MAX_WPM, MIN_WPM};
+use crate::config::Config;
+
+/// The engine spends `delay / 2` on the keystroke and `delay` after it, so an
+/// observed interval is about 1.5x the delay the model produces
+const ENGINE_SPAN: f64 = 1.5;
+
+/// Regular keystrokes run this much faster than the base delay once word
+/// momentum, digraphs and hand alternation are applied
+const MODEL_SPEEDUP: f64 = 0.92;
+
+/// Spread of regular intervals the timing model produces with no variance
+const MODEL_CV: f64 = 0.32;
+
+/// Fewest regular intervals needed for a fit
+const MIN_SAMPLES: usize = 20;
+
+/// Fewest samples for an n-gram to be reported
+const MIN_NGRAM_SAMPLES: usize = 3;
+
+/// Longest texts compared against the reference for uncorrected mistakes
+const MAX_REFERENCE_CHARS: usize = 5000;
+
+/// One recorded key event. Matches the keystroke log export, so a log from a
+/// previous run (or a typing test) can be fed straight back in.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedKey {
+    /// Milliseconds since the recording started
+    pub t_ms: u64,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+/// Relative speed of an n-gram's last keystroke compared to regular typing.
+/// Same shape as an n-gram table entry, so calibration output can be used as
+/// the config's `ngramFile`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct NgramFit {
+    /// Median interval divided by the overall median (below 1 is faster)
+    pub ratio: f64,
+    /// Spread of that ratio
+    pub spread: f64,
+    pub samples: usize,
+}
+
+/// A regular interval and the keys around it
+struct Interval {
+    before_prev: Option<char>,
+    prev: char,
+    curr: char,
+    ms: f64,
+}
+
+/// Number of intervals each parameter was fitted from
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct FitSamples {
+    pub typing: usize,
+    pub punctuation: usize,
+    pub paragraph: usize,
+    pub thinking: usize,
+    pub corrections: usize,
+}
+
+/// How well the recording constrained the fitted profile
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FitReport {
+    pub keystrokes: usize,
+    pub duration_ms: u64,
+    /// Speed actually reached in the recording (final text over total time)
+    pub observed_wpm: f64,
+    /// Median time between regular keystrokes
+    pub median_interval_ms: f64,
+    /// Standard deviation of regular intervals relative to their mean
+    pub observed_cv: f64,
+    pub samples: FitSamples,
+    /// Parameters kept from the base config and why
+    pub warnings: Vec<String>,
+}
+
+/// A fitted profile
+#[derive(Debug, Clone, Serialize)]
+pub struct Calibration {
+    pub config: Config,
+    pub digraphs: BTreeMap<String, NgramFit>,
+    pub trigraphs: BTreeMap<String, NgramFit>,
+    pub report: FitReport,
+}
+
+/// Parse a recording given as a JSON array or as JSON Lines
+pub fn parse_recording(text: &str) -> Result<Vec<RecordedKey>, String> {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('[') {
+        return serde_json::from_str(trimmed).map_err(|e| format!("Invalid recording: {}", e));
+    }
+
+    trimmed
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| format!("Invalid recording at line {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
+/// Fit a profile to `keys`. Parameters the recording says nothing about are
+/// taken from `base`. With a `reference` text, mistakes left uncorrected are
+/// counted too, which makes the correction rate fittable.
+pub fn calibrate(keys: &[RecordedKey], reference: Option<&str>, base: &Config) -> Result<Calibration, String> {
+    let mut keys: Vec<RecordedKey> = keys
+        .iter()
+        .filter(|k| !matches!(k.action, Action::Wait | Action::Shortcut(_)))
+        .copied()
+        .collect();
+    keys.sort_by_key(|k| k.t_ms);
+
+    let mut config = base.clone();
+    let mut report = FitReport {
+        keystrokes: keys.len(),
+        duration_ms: match (keys.first(), keys.last()) {
+            (Some(first), Some(last)) => last.t_ms - first.t_ms,
+            _ => 0,
+        },
+        ..Default::default()
+    };
+
+    let typed_char = |i: usize| match keys.get(i) {
+        Some(RecordedKey {
+            action: Action::Type(c),
+            ..
+        }) => Some(*c),
+        _ => None,
+    };
+    let is_sentence_end = |c: char| matches!(c, '.' | '!' | '?');
+    let is_clause_end = |c: char| matches!(c, ',' | ';' | ':');
+    let is_pause_point = |c: char| c == '\n' || is_sentence_end(c) || is_clause_end(c);
+
+    // The engine spends a position's delay after typing it, while people
+    // pause before or after the key. Pauses are therefore measured over the
+    // two intervals around the punctuation or line break, and those intervals
+    // are kept out of the regular samples.
+    let mut regular: Vec<Interval> = Vec::new();
+    let mut sentence: Vec<f64> = Vec::new();
+    let mut clause: Vec<f64> = Vec::new();
+    let mut paragraph: Vec<(Option<char>, f64)> = Vec::new();
+    let mut typed: Vec<char> = Vec::new();
+    let mut corrections = 0;
+
+    for (i, key) in keys.iter().enumerate() {
+        match key.action {
+            Action::Type(c) => {
+                let prev = i.checked_sub(1).and_then(typed_char);
+                let before_prev = i.checked_sub(2).and_then(typed_char);
+                let next = typed_char(i + 1);
+                let after_next = typed_char(i + 2);
+
+                if c == '\n' {
+                    if let (Some(_), Some(_)) = (prev, next) {
+                        let span = keys[i + 1].t_ms - keys[i - 1].t_ms;
+                        paragraph.push((prev, span as f64));
+                    }
+                } else if is_sentence_end(c) || is_clause_end(c) {
+                    if let (Some(n), Some(a)) = (next, after_next) {
+                        if n != '\n' && a != '\n' {
+                            let span = (keys[i + 2].t_ms - key.t_ms) as f64;
+                            if is_sentence_end(c) {
+                                sentence.push(span);
+                            } else {
+                                clause.push(span);
+                            }
+                        }
+                    }
+                }
+
+                if let Some(p) = prev {
+                    let near_pause = is_pause_point(p) || before_prev.is_some_and(is_pause_point);
+                    if !near_pause && c != '\n' {
+                        regular.push(Interval {
+                            before_prev,
+                            prev: p,
+                            curr: c,
+                            ms: (key.t_ms - keys[i - 1].t_ms) as f64,
+                        });
+                    }
+                }
+                typed.push(c);
+            }
+            Action::Backspace => {
+                let run_start = i == 0 || keys[i - 1].action != Action::Backspace;
+                if run_start {
+                    corrections += 1;
+                }
+                typed.pop();
+            }
+            Action::Wait | Action::Shortcut(_) => {}
+        }
+    }
+
+    let mut all: Vec<f64> = regular.iter().map(|interval| interval.ms).collect();
+    if all.len() < MIN_SAMPLES {
+        return Err(format!(
+            "Recording too short: {} regular keystroke intervals, need at least {}",
+            all.len(),
+            MIN_SAMPLES
+        ));
+    }
+    let median_all = median(&mut all);
+
+    // Long gaps inside regular typing are thinking pauses
+    let threshold = (median_all * 4.0).max(1000.0);
+    let mut typing: Vec<f64> = all.iter().copied().filter(|&t| t <= threshold).collect();
+    let median_interval = median(&mut typing);
+    let mut thinking: Vec<f64> = all
+        .iter()
+        .filter(|&&t| t > threshold)
+        .map(|t| t - median_interval)
+        .collect();
+    report.median_interval_ms = median_interval;
+
+    // === SPEED AND VARIANCE ===
+
+    let base_delay = median_interval / ENGINE_SPAN / MODEL_SPEEDUP;
+    config.base_wpm = ((12000.0 / base_delay.max(1.0)).round() as u32).clamp(MIN_WPM, MAX_WPM);
+
+    // Word rhythm, hand alternation and bursts already spread the intervals;
+    // only the spread beyond that is attributed to the variance setting,
+    // which the engine applies at half strength
+    let mean = typing.iter().sum::<f64>() / typing.len() as f64;
+    let std_dev = (typing.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / typing.len() as f64).sqrt();
+    report.observed_cv = round2(std_dev / mean);
+    let extra_cv = (report.observed_cv.powi(2) - MODEL_CV.powi(2)).max(0.0).sqrt();
+    config.wpm_variance = round2((extra_cv * 2.0).clamp(0.05, 0.8));
+    report.samples.typing = typing.len();
+
+    // === PAUSES ===
+
+    // Measured pauses already include composing the next unit, so fold
+    // composition into the punctuation and paragraph pauses
+    config.composition_pause = 0;
+
+    // Punctuation is followed by a space, which is slower than other keys
+    let mut after_space: Vec<f64> = regular
+        .iter()
+        .filter(|interval| interval.prev == ' ' && interval.ms <= threshold)
+        .map(|interval| interval.ms)
+        .collect();
+    let space_interval = if after_space.is_empty() {
+        median_interval
+    } else {
+        median(&mut after_space)
+    };
+
+    // Clause punctuation gets half the sentence pause in the model
+    let punctuation_extra = |span: f64| span - median_interval - space_interval;
+    let mut punctuation: Vec<f64> = sentence
+        .iter()
+        .map(|&t| punctuation_extra(t))
+        .chain(clause.iter().map(|&t| punctuation_extra(t) * 2.0))
+        .collect();
+    report.samples.punctuation = punctuation.len();
+    if punctuation.is_empty() {
+        report
+            .warnings
+            .push("No pauses after punctuation; kept the punctuation pause".to_string());
+    } else {
+        config.punctuation_pause = (median(&mut punctuation) / ENGINE_SPAN).max(0.0) as u64;
+    }
+
+    // A line break right after punctuation also carries the punctuation pause
+    let punctuation_span = config.punctuation_pause as f64 * ENGINE_SPAN;
+    let span_extra = |span: f64| span - 2.0 * median_interval;
+    let mut paragraph: Vec<f64> = paragraph
+        .iter()
+        .map(|&(prev, span)| match prev {
+            Some(p) if is_sentence_end(p) => span_extra(span) - punctuation_span,
+            Some(p) if is_clause_end(p) => span_extra(span) - punctuation_span / 2.0,
+            _ => span_extra(span),
+        })
+        .collect();
+    report.samples.paragraph = paragraph.len();
+    if paragraph.is_empty() {
+        report
+            .warnings
+            .push("No line breaks; kept the paragraph pause".to_string());
+    } else {
+        config.paragraph_pause = (median(&mut paragraph) / ENGINE_SPAN).max(0.0) as u64;
+    }
+
+    report.samples.thinking = thinking.len();
+    config.thinking_pause_chance = round3(thinking.len() as f64 / all.len() as f64);
+    if thinking.is_empty() {
+        report
+            .warnings
+            .push("No thinking pauses; kept the thinking pause duration".to_string());
+    } else {
+        config.thinking_pause_duration = (median(&mut thinking) / ENGINE_SPAN) as u64;
+    }
+
+    // === MISTAKES ===
+
+    report.samples.corrections = corrections;
+    let final_chars = typed.len().max(1);
+    let uncorrected = match reference {
+        Some(reference) if typed.len() <= MAX_REFERENCE_CHARS && reference.chars().count() <= MAX_REFERENCE_CHARS => {
+            let expected: Vec<char> = reference.chars().collect();
+            Some(edit_distance(&typed, &expected))
+        }
+        Some(_) => {
+            report.warnings.push(format!(
+                "Text longer than {} characters; not compared with the reference",
+                MAX_REFERENCE_CHARS
+            ));
+            None
+        }
+        None => None,
+    };
+
+    match uncorrected {
+        Some(uncorrected) => {
+            let mistakes = corrections + uncorrected;
+            config.mistake_rate = round3(mistakes as f64 / final_chars as f64);
+            if mistakes > 0 {
+                config.correction_rate = round2(corrections as f64 / mistakes as f64);
+            }
+        }
+        None => {
+            // Without the intended text only corrected mistakes are visible
+            config.mistake_rate = round3(corrections as f64 / config.correction_rate.max(0.1) / final_chars as f64);
+            report
+                .warnings
+                .push("No reference text; kept the correction rate".to_string());
+        }
+    }
+
+    report.observed_wpm = if report.duration_ms > 0 {
+        (typed.len() as f64 / 5.0) / (report.duration_ms as f64 / 60_000.0)
+    } else {
+        0.0
+    };
+
+    Ok(Calibration {
+        config,
+        digraphs: fit_ngrams(&regular, false, threshold, median_interval),
+        trigraphs: fit_ngrams(&regular, true, threshold, median_interval),
+        report,
+    })
+}
+
+/// Per-n-gram speed relative to the median interval
+fn fit_ngrams(
+    regular: &[Interval],
+    trigraphs: bool,
+    threshold: f64,
+    median_interval: f64,
+) -> BTreeMap<String, NgramFit> {
+    let mut by_ngram: HashMap<String, Vec<f64>> = HashMap::new();
+    for interval in regular.iter().filter(|i| i.ms <= threshold) {
+        let chars = match (trigraphs, interval.before_prev) {
+            (true, Some(b)) => vec![b, interval.prev, interval.curr],
+            (true, None) => continue,
+            (false, _) => vec![interval.prev, interval.curr],
+        };
+        if chars.iter().all(|c| c.is_alphabetic()) {
+            let ngram: String = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+            by_ngram.entry(ngram).or_default().push(interval.ms);
+        }
+    }
+
+    let base = median_interval.max(1.0);
+    by_ngram
+        .into_iter()
+        .filter(|(_, samples)| samples.len() >= MIN_NGRAM_SAMPLES)
+        .map(|(ngram, mut samples)| {
+            let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+            let variance = samples.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+            let fit = NgramFit {
+                ratio: round2(median(&mut samples) / base),
+                spread: round2(variance.sqrt() / base),
+                samples: samples.len(),
+            };
+            (ngram, fit)
+        })
+        .collect()
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Edit distance between two character sequences, counting a swap of
+/// neighbouring characters as one edit (like a transposition mistake)
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for i in 0..a.len() {
+        curr[0] = i + 1;
+        for j in 0..b.len() {
+            let cost = usize::from(a[i] != b[j]);
+            let mut best = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+            if i > 0 && j > 0 && a[i] == b[j - 1] && a[i - 1] == b[j] {
+                best = best.min(before[j - 1] + 1);
+            }
+            curr[j + 1] = best;
+        }
+        std::mem::swap(&mut before, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn round3(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keys for `text` at a steady `interval_ms`, with `\u{8}` as backspace
+    fn record(text: &str, interval_ms: u64) -> Vec<RecordedKey> {
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| RecordedKey {
+                t_ms: i as u64 * interval_ms,
+                action: if c == '\u{8}' { Action::Backspace } else { Action::Type(c) },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recordings_parse_as_an_array_or_lines() {
+        let lines = "{\"tMs\": 0, \"kind\": \"type\", \"char\": \"a\"}\n\n{\"tMs\": 90, \"kind\": \"backspace\"}\n";
+        let keys = parse_recording(lines).unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[1].t_ms, 90);
+        assert_eq!(keys[1].action, Action::Backspace);
+
+        let array = "[{\"tMs\": 0, \"kind\": \"type\", \"char\": \"a\"}]";
+        assert_eq!(parse_recording(array).unwrap()[0].action, Action::Type('a'));
+
+        let error = parse_recording("{\"tMs\": 0, \"kind\": \"type\", \"char\": \"a\"}\n{\"tMs\": 1}\n").unwrap_err();
+        assert!(error.starts_with("Invalid recording at line 2"), "{}", error);
+    }
+
+    #[test]
+    fn short_recordings_are_refused() {
+        let error = calibrate(&record("hello there", 150), None, &Config::default()).unwrap_err();
+        assert!(error.starts_with("Recording too short"), "{}", error);
+    }
+
+    #[test]
+    fn steady_typing_fits_the_speed() {
+        let text = "the cat sat on the mat ".repeat(3);
+        let calibration = calibrate(&record(&text, 150), None, &Config::default()).unwrap();
+        assert_eq!(calibration.report.median_interval_ms, 150.0);
+        assert_eq!(calibration.report.observed_cv, 0.0);
+        // 150 ms observed is a 150 / 1.5 / 0.92 ms model delay
+        assert_eq!(calibration.config.base_wpm, 110);
+        assert_eq!(calibration.config.mistake_rate, 0.0);
+        assert_eq!(calibration.report.samples.thinking, 0);
+        assert!(!calibration.report.warnings.is_empty());
+
+        let th = calibration.digraphs["th"];
+        assert_eq!((th.ratio, th.samples), (1.0, 6));
+        assert!(calibration.trigraphs.contains_key("the"));
+        assert!(!calibration.digraphs.contains_key("e "));
+    }
+
+    #[test]
+    fn long_gaps_are_thinking_pauses() {
+        let text = "the cat sat on the mat ".repeat(3);
+        let mut keys = record(&text, 150);
+        for key in &mut keys[30..] {
+            key.t_ms += 3000;
+        }
+        let calibration = calibrate(&keys, None, &Config::default()).unwrap();
+        assert_eq!(calibration.report.samples.thinking, 1);
+        assert_eq!(calibration.config.thinking_pause_duration, 2000);
+    }
+
+    #[test]
+    fn a_reference_separates_corrected_and_uncorrected_mistakes() {
+        let text = "the cat sat on the mqt ".repeat(2) + "thw\u{8}e cat sat on the mat ";
+        let reference = "the cat sat on the mat ".repeat(3);
+        let calibration = calibrate(&record(&text, 150), Some(&reference), &Config::default()).unwrap();
+        assert_eq!(calibration.report.samples.corrections, 1);
+        assert_eq!(calibration.config.mistake_rate, round3(3.0 / 69.0));
+        assert_eq!(calibration.config.correction_rate, 0.33);
+    }
+
+    #[test]
+    fn swapped_neighbours_are_one_edit() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("teh"), &chars("the")), 1);
+        assert_eq!(edit_distance(&chars("abc"), &chars("")), 3);
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+    }
+
+    #[test]
+    fn median_of_even_and_odd_counts() {
+        assert_eq!(median(&mut [3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&mut [4.0, 1.0, 3.0, 2.0]), 2.5);
+        assert_eq!(median(&mut []), 0.0);
+    }
+}
@@ -0,0 +1,385 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Programming languages with a code timing model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CodeLanguage {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    C,
+    Cpp,
+    Java,
+    Kotlin,
+    CSharp,
+    Go,
+    Shell,
+}
+
+impl CodeLanguage {
+    /// Detect the language from a file's extension
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        Some(match ext.as_str() {
+            "rs" => Self::Rust,
+            "py" | "pyw" => Self::Python,
+            "js" | "jsx" | "mjs" | "cjs" => Self::JavaScript,
+            "ts" | "tsx" | "mts" | "cts" => Self::TypeScript,
+            "c" | "h" => Self::C,
+            "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => Self::Cpp,
+            "java" => Self::Java,
+            "kt" | "kts" => Self::Kotlin,
+            "cs" => Self::CSharp,
+            "go" => Self::Go,
+            "sh" | "bash" | "zsh" => Self::Shell,
+            _ => return None,
+        })
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &[
+                "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false",
+                "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+                "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+                "Some", "None", "Ok", "Err", "Option", "Result", "String", "Vec",
+            ],
+            Self::Python => &[
+                "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def",
+                "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+                "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield", "self",
+                "print", "len", "range",
+            ],
+            Self::JavaScript | Self::TypeScript => &[
+                "async", "await", "break", "case", "catch", "class", "const", "continue", "default", "delete", "do",
+                "else", "export", "extends", "false", "finally", "for", "from", "function", "if", "import", "in",
+                "instanceof", "let", "new", "null", "of", "return", "static", "super", "switch", "this", "throw",
+                "true", "try", "typeof", "undefined", "var", "void", "while", "yield", "interface", "type", "enum",
+                "implements", "private", "public", "readonly", "string", "number", "boolean", "console",
+            ],
+            Self::C | Self::Cpp => &[
+                "auto", "bool", "break", "case", "char", "class", "const", "continue", "default", "delete", "do",
+                "double", "else", "enum", "false", "float", "for", "if", "include", "define", "int", "long",
+                "namespace", "new", "nullptr", "private", "public", "return", "short", "signed", "sizeof", "static",
+                "std", "struct", "switch", "template", "this", "true", "typedef", "unsigned", "using", "void",
+                "while",
+            ],
+            Self::Java => &[
+                "abstract", "boolean", "break", "case", "catch", "class", "double", "else", "extends", "false",
+                "final", "finally", "float", "for", "if", "implements", "import", "int", "interface", "long", "new",
+                "null", "package", "private", "protected", "public", "return", "static", "String", "super", "switch",
+                "this", "throw", "throws", "true", "try", "var", "void", "while",
+            ],
+            Self::Kotlin => &[
+                "as", "break", "by", "catch", "class", "companion", "continue", "data", "do", "else", "enum",
+                "false", "finally", "for", "fun", "if", "import", "in", "init", "interface", "internal", "is",
+                "lateinit", "null", "object", "open", "override", "package", "private", "protected", "public",
+                "return", "sealed", "super", "suspend", "this", "throw", "true", "try", "val", "var", "when",
+                "while", "Boolean", "Int", "String", "Unit", "listOf", "println",
+            ],
+            Self::CSharp => &[
+                "abstract", "as", "async", "await", "base", "bool", "break", "case", "catch", "class", "const",
+                "continue", "default", "do", "double", "else", "enum", "false", "finally", "float", "for",
+                "foreach", "get", "if", "in", "int", "interface", "internal", "is", "long", "namespace", "new",
+                "null", "object", "out", "override", "private", "protected", "public", "readonly", "ref", "return",
+                "set", "static", "string", "struct", "switch", "this", "throw", "true", "try", "using", "var",
+                "virtual", "void", "while", "Console",
+            ],
+            Self::Go => &[
+                "break", "case", "chan", "const", "continue", "default", "defer", "else", "err", "false", "for",
+                "func", "go", "if", "import", "int", "interface", "map", "nil", "package", "range", "return",
+                "select", "string", "struct", "switch", "true", "type", "var",
+            ],
+            Self::Shell => &[
+                "case", "cd", "do", "done", "echo", "elif", "else", "esac", "exit", "export", "fi", "for",
+                "function", "if", "in", "local", "read", "return", "then", "while",
+            ],
+        }
+    }
+
+    fn line_comment(&self) -> &'static str {
+        match self {
+            Self::Python | Self::Shell => "#",
+            _ => "//",
+        }
+    }
+
+    fn block_comments(&self) -> bool {
+        !matches!(self, Self::Python | Self::Shell)
+    }
+}
+
+/// What a character of source code is part of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Keyword,
+    Identifier,
+    Number,
+    Symbol,
+    /// Inside a string literal or comment, typed like prose
+    Text,
+    /// Leading whitespace of a line
+    Indent,
+    Space,
+    Newline,
+}
+
+/// Tokenized source code
+#[derive(Debug, Default)]
+pub struct CodeModel {
+    tokens: Vec<Token>,
+    /// First character of each statement line, with a pause weight
+    statements: HashMap<usize, f64>,
+}
+
+impl CodeModel {
+    pub fn analyze(chars: &[char], language: CodeLanguage) -> Self {
+        let keywords = language.keywords();
+        let comment: Vec<char> = language.line_comment().chars().collect();
+        let mut tokens = vec![Token::Space; chars.len()];
+        let mut i = 0;
+
+        let fill = |tokens: &mut Vec<Token>, range: std::ops::Range<usize>, token: Token| {
+            tokens[range].iter_mut().for_each(|t| *t = token);
+        };
+
+        while i < chars.len() {
+            let c = chars[i];
+            let start = i;
+
+            if c == '\n' {
+                tokens[i] = Token::Newline;
+                i += 1;
+                while i < chars.len() && matches!(chars[i], ' ' | '\t') {
+                    tokens[i] = Token::Indent;
+                    i += 1;
+                }
+            } else if chars[i..].starts_with(&comment) {
+                i = (i..chars.len()).find(|&j| chars[j] == '\n').unwrap_or(chars.len());
+                fill(&mut tokens, start..i, Token::Text);
+            } else if language.block_comments() && chars[i..].starts_with(&['/', '*']) {
+                i = (i + 2..chars.len())
+                    .find(|&j| chars[j..].starts_with(&['*', '/']))
+                    .map_or(chars.len(), |j| j + 2);
+                fill(&mut tokens, start..i, Token::Text);
+            } else if is_string_quote(chars, i, language) {
+                // Opening and closing quotes are symbols, the rest is text
+                let end = string_end(chars, i);
+                fill(&mut tokens, i + 1..end, Token::Text);
+                tokens[i] = Token::Symbol;
+                if end > i + 1 && chars[end - 1] == c {
+                    tokens[end - 1] = Token::Symbol;
+                }
+                i = end;
+            } else if c.is_alphabetic() || c == '_' {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let token = if keywords.contains(&word.as_str()) {
+                    Token::Keyword
+                } else {
+                    Token::Identifier
+                };
+                fill(&mut tokens, start..i, token);
+            } else if c.is_ascii_digit() {
+                while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '.' | '_')) {
+                    i += 1;
+                }
+                fill(&mut tokens, start..i, Token::Number);
+            } else {
+                tokens[i] = if c.is_whitespace() { Token::Space } else { Token::Symbol };
+                i += 1;
+            }
+        }
+
+        let statements = statement_starts(chars, &tokens, &comment);
+        Self { tokens, statements }
+    }
+
+    pub fn token(&self, index: usize) -> Token {
+        self.tokens.get(index).copied().unwrap_or(Token::Space)
+    }
+
+    /// Pause weight if a statement starts at `index`
+    pub fn statement_at(&self, index: usize) -> Option<f64> {
+        self.statements.get(&index).copied()
+    }
+}
+
+/// Whether a string literal opens at `chars[i]`. A Rust `'` is only a
+/// character literal, not a lifetime.
+fn is_string_quote(chars: &[char], i: usize, language: CodeLanguage) -> bool {
+    match chars[i] {
+        '"' => true,
+        '`' => matches!(language, CodeLanguage::JavaScript | CodeLanguage::TypeScript | CodeLanguage::Go | CodeLanguage::Shell),
+        '\'' if language == CodeLanguage::Rust => {
+            chars.get(i + 1) == Some(&'\\') || chars.get(i + 2) == Some(&'\'')
+        }
+        '\'' => true,
+        _ => false,
+    }
+}
+
+/// Index after the closing quote of the literal opened at `chars[open]`.
+/// Only backtick strings span lines.
+fn string_end(chars: &[char], open: usize) -> usize {
+    let quote = chars[open];
+    let mut i = open + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '\n' if quote != '`' => return i,
+            c if c == quote => return i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// First character of each non-blank line, weighted by what comes before:
+/// opening a block or starting after a blank line takes more thought, a
+/// closing bracket less
+fn statement_starts(chars: &[char], tokens: &[Token], comment: &[char]) -> HashMap<usize, f64> {
+    let mut statements = HashMap::new();
+    let mut last_char: Option<char> = None;
+    let mut blank_before = false;
+    let mut line_start = 0;
+
+    while line_start < chars.len() {
+        let line_end = (line_start..chars.len())
+            .find(|&i| chars[i] == '\n')
+            .unwrap_or(chars.len());
+        let first = (line_start..line_end).find(|&i| !chars[i].is_whitespace());
+        let last = (line_start..line_end).rev().find(|&i| !chars[i].is_whitespace());
+
+        match (first, last) {
+            (Some(first), Some(last)) => {
+                // Lines continuing a block comment or multi-line string are
+                // not statements; lines starting a comment are
+                let starts_comment = chars[first..].starts_with(comment) || chars[first..].starts_with(&['/', '*']);
+                if first > 0 && (tokens[first] != Token::Text || starts_comment) {
+                    let mut weight = if matches!(chars[first], '}' | ')' | ']') { 0.4 } else { 1.0 };
+                    if matches!(last_char, Some('{' | ':' | '(' | '[')) {
+                        weight *= 1.4;
+                    }
+                    if blank_before {
+                        weight *= 1.3;
+                    }
+                    statements.insert(first, weight);
+                }
+                last_char = Some(chars[last]);
+                blank_before = false;
+            }
+            _ => blank_before = true,
+        }
+        line_start = line_end + 1;
+    }
+    statements
+}
+
+/// Delay multiplier for typing the symbol `c` after `prev`. Symbols needing
+/// shift and far reaches are slow; the second key of a familiar operator
+/// (`=>`, `::`, `->`, `!=`) rolls.
+pub fn symbol_cost(prev: Option<char>, c: char) -> f64 {
+    const OPERATORS: &[[char; 2]] = &[
+        ['=', '>'], [':', ':'], ['-', '>'], ['!', '='], ['=', '='], ['<', '='], ['>', '='], ['&', '&'],
+        ['|', '|'], ['+', '='], ['-', '='], ['/', '/'], ['*', '/'], ['/', '*'], ['+', '+'], ['(', ')'],
+        ['[', ']'], ['{', '}'], ['.', '.'],
+    ];
+    if let Some(p) = prev {
+        if OPERATORS.contains(&[p, c]) {
+            return 0.8;
+        }
+    }
+    match c {
+        '{' | '}' | '<' | '>' | '|' | '~' | '^' | '`' => 1.45,
+        '(' | ')' | '_' | '+' | ':' | '"' | '!' | '@' | '#' | '$' | '%' | '&' | '*' | '?' => 1.3,
+        '[' | ']' | '\\' | '=' => 1.15,
+        _ => 1.05,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One letter per character: Keyword, Identifier, Number, Symbol, Text,
+    /// `>` indent, `|` newline
+    fn tokens(code: &str, language: CodeLanguage) -> String {
+        let chars: Vec<char> = code.chars().collect();
+        let model = CodeModel::analyze(&chars, language);
+        (0..chars.len())
+            .map(|i| match model.token(i) {
+                Token::Keyword => 'K',
+                Token::Identifier => 'I',
+                Token::Number => 'N',
+                Token::Symbol => 'S',
+                Token::Text => 'T',
+                Token::Indent => '>',
+                Token::Space => ' ',
+                Token::Newline => '|',
+            })
+            .collect()
+    }
+
+    const RUST: &str = "fn main() {\n    let x = 42; // note\n    s(\"a b\", 'c', &'a);\n\n}\n";
+
+    #[test]
+    fn languages_come_from_the_extension() {
+        assert_eq!(CodeLanguage::from_path("src/Main.KT"), Some(CodeLanguage::Kotlin));
+        assert_eq!(CodeLanguage::from_path("a.tsx"), Some(CodeLanguage::TypeScript));
+        assert_eq!(CodeLanguage::from_path("notes.md"), None);
+        assert_eq!(CodeLanguage::from_path("Makefile"), None);
+    }
+
+    #[test]
+    fn rust_tokens() {
+        assert_eq!(
+            tokens(RUST, CodeLanguage::Rust),
+            "KK IIIISS S|>>>>KKK I S NNS TTTTTTT|>>>>ISSTTTSS STSS SSISS||S|"
+        );
+    }
+
+    #[test]
+    fn quotes_and_comments_depend_on_the_language() {
+        assert_eq!(tokens("x = 'a b' # c", CodeLanguage::Python), "I S STTTS TTT");
+        assert_eq!(tokens("a /* b */ c", CodeLanguage::C), "I TTTTTTT I");
+        assert_eq!(tokens("a `b\nc` d", CodeLanguage::Go), "I STTTS I");
+        assert_eq!(tokens("\"open\nx", CodeLanguage::Java), "STTTT|I");
+    }
+
+    #[test]
+    fn statements_weigh_what_comes_before() {
+        let chars: Vec<char> = RUST.chars().collect();
+        let model = CodeModel::analyze(&chars, CodeLanguage::Rust);
+        let at = |needle: &str| model.statement_at(RUST.find(needle).unwrap());
+        assert_eq!(at("fn"), None);
+        assert_eq!(at("let"), Some(1.4));
+        assert_eq!(at("s("), Some(1.0));
+        assert_eq!(at("}\n"), Some(0.4 * 1.3));
+        assert_eq!(at("x ="), None);
+    }
+
+    #[test]
+    fn block_comment_lines_are_not_statements() {
+        let code = "a();\n/* one\n   two */\nb();\n";
+        let chars: Vec<char> = code.chars().collect();
+        let model = CodeModel::analyze(&chars, CodeLanguage::C);
+        assert!(model.statement_at(code.find("/*").unwrap()).is_some());
+        assert!(model.statement_at(code.find("two").unwrap()).is_none());
+        assert!(model.statement_at(code.find("b()").unwrap()).is_some());
+    }
+
+    #[test]
+    fn familiar_operators_roll() {
+        assert_eq!(symbol_cost(Some('='), '>'), 0.8);
+        assert_eq!(symbol_cost(Some('a'), '>'), 1.45);
+        assert_eq!(symbol_cost(None, '('), 1.3);
+        assert_eq!(symbol_cost(None, ','), 1.05);
+    }
+}
@@ -0,0 +1,130 @@
+use rand::Rng;
+use rand_distr::{Distribution, Exp, Gamma, LogNormal, Normal};
+use serde::{Deserialize, Serialize};
+
+/// Shape of the random spread applied to delays. Every family is
+/// parameterised by the mean (the planned delay) and the coefficient of
+/// variation (the configured variance), so switching family keeps the
+/// average speed and spread and only changes the shape.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "family")]
+pub enum IntervalDistribution {
+    /// Symmetric, truncated to 50–200% of the delay
+    #[default]
+    Normal,
+    /// Right-skewed; the usual fit for inter-key intervals
+    LogNormal,
+    /// Normal plus an exponential tail. `tail` is the share of the variance
+    /// that comes from the tail (0–1).
+    ExGaussian {
+        #[serde(default = "default_tail")]
+        tail: f64,
+    },
+    /// Right-skewed, lighter tail than log-normal
+    Gamma,
+}
+
+fn default_tail() -> f64 {
+    0.5
+}
+
+impl IntervalDistribution {
+    /// Draw a value with the given mean and coefficient of variation
+    pub fn sample(&self, mean: f64, cv: f64, rng: &mut impl Rng) -> f64 {
+        if mean <= 0.0 || cv <= 0.0 {
+            return mean;
+        }
+        let sd = mean * cv;
+
+        let sampled = match *self {
+            Self::Normal => Normal::new(mean, sd).ok().map(|d| d.sample(rng)),
+            Self::LogNormal => {
+                let sigma2 = (1.0 + cv * cv).ln();
+                LogNormal::new(mean.ln() - sigma2 / 2.0, sigma2.sqrt())
+                    .ok()
+                    .map(|d| d.sample(rng))
+            }
+            Self::ExGaussian { tail } => {
+                let tail = tail.clamp(0.0, 1.0);
+                let tau = sd * tail.sqrt();
+                let sigma = sd * (1.0 - tail).sqrt();
+                let normal = Normal::new(mean - tau, sigma).ok().map(|d| d.sample(rng));
+                let exp = if tau > 0.0 {
+                    Exp::new(1.0 / tau).ok().map(|d| d.sample(rng))
+                } else {
+                    Some(0.0)
+                };
+                normal.zip(exp).map(|(n, e)| n + e)
+            }
+            Self::Gamma => {
+                let shape = 1.0 / (cv * cv);
+                Gamma::new(shape, mean / shape).ok().map(|d| d.sample(rng))
+            }
+        };
+        sampled.unwrap_or(mean)
+    }
+
+    /// Range samples are clamped to, relative to the mean. The normal family
+    /// keeps its original tight bounds; the skewed ones need room for a tail.
+    pub fn bounds(&self) -> (f64, f64) {
+        match self {
+            Self::Normal => (0.5, 2.0),
+            _ => (0.25, 5.0),
+        }
+    }
+
+    /// Draw a value and clamp it to [`Self::bounds`]
+    pub fn sample_clamped(&self, mean: f64, cv: f64, rng: &mut impl Rng) -> f64 {
+        let (low, high) = self.bounds();
+        self.sample(mean, cv, rng).clamp(mean * low, mean * high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SAMPLES: usize = 100_000;
+    const MEAN: f64 = 100.0;
+    const CV: f64 = 0.3;
+
+    /// Sample mean and standard deviation of `distribution`
+    fn moments(distribution: IntervalDistribution) -> (f64, f64) {
+        let mut rng = StdRng::seed_from_u64(7);
+        let samples: Vec<f64> =
+            (0..SAMPLES).map(|_| distribution.sample(MEAN, CV, &mut rng)).collect();
+        let mean = samples.iter().sum::<f64>() / SAMPLES as f64;
+        let variance =
+            samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (SAMPLES - 1) as f64;
+        (mean, variance.sqrt())
+    }
+
+    fn assert_moments(distribution: IntervalDistribution) {
+        let (mean, sd) = moments(distribution);
+        assert!((mean / MEAN - 1.0).abs() < 0.01, "{:?}: mean {}", distribution, mean);
+        assert!((sd / (MEAN * CV) - 1.0).abs() < 0.03, "{:?}: sd {}", distribution, sd);
+    }
+
+    #[test]
+    fn normal_moments() {
+        assert_moments(IntervalDistribution::Normal);
+    }
+
+    #[test]
+    fn log_normal_moments() {
+        assert_moments(IntervalDistribution::LogNormal);
+    }
+
+    #[test]
+    fn ex_gaussian_moments() {
+        assert_moments(IntervalDistribution::ExGaussian { tail: 0.5 });
+        assert_moments(IntervalDistribution::ExGaussian { tail: 0.9 });
+    }
+
+    #[test]
+    fn gamma_moments() {
+        assert_moments(IntervalDistribution::Gamma);
+    }
+}
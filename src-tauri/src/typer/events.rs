@@ -0,0 +1,29 @@
+use serde::Serialize;
+#[cfg(feature = "app")]
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// Destination for the events emitted by the typing engine: `typing-progress`,
+/// `typing-state-changed`, `countdown-tick`, `typing-error`, `typing-undone`,
+/// `config-changed`, the queue events (`queue-changed`, `queue-item-started`,
+/// `queue-item-finished`), the trigger events (`trigger-waiting`,
+/// `trigger-tick`, `trigger-fired`), `break-started`, `break-ended`,
+/// `focus-lost`, `focus-restored`, `focus-guard-unavailable`,
+/// `variables-required`, `content-loaded`, `content-changed` and
+/// `session-report`
+pub trait EventSink: Send + Sync + 'static {
+    fn emit_event(&self, event: &str, payload: serde_json::Value);
+}
+
+#[cfg(feature = "app")]
+impl<R: Runtime> EventSink for AppHandle<R> {
+    fn emit_event(&self, event: &str, payload: serde_json::Value) {
+        let _ = self.emit(event, payload);
+    }
+}
+
+/// An engine event as delivered to subscribers of [`super::TypingEngine::subscribe`]
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineEvent {
+    pub event: String,
+    pub payload: serde_json::Value,
+}
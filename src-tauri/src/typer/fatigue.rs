@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How a warmup or fatigue effect builds up over its time span
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Curve {
+    Linear,
+    /// Fast at first, then levelling off
+    #[default]
+    Exponential,
+    /// Slow start and end, fastest in the middle
+    Sigmoid,
+}
+
+impl Curve {
+    /// Progress through the effect (0–1) at fraction `x` of its span
+    pub fn at(&self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => x,
+            Self::Exponential => (1.0 - (-3.0 * x).exp()) / (1.0 - (-3.0f64).exp()),
+            Self::Sigmoid => x * x * (3.0 - 2.0 * x),
+        }
+    }
+}
+
+/// Warmup and fatigue over time spent typing
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FatigueModel {
+    /// Extra delay at the very start (0.35 is 35% slower), fading out
+    pub warmup_slowdown: f64,
+    /// Seconds of typing until fully warmed up
+    pub warmup_secs: f64,
+    pub warmup_curve: Curve,
+    /// Extra delay once fully fatigued (0 disables fatigue)
+    pub fatigue_slowdown: f64,
+    /// Seconds of typing until fully fatigued
+    pub fatigue_secs: f64,
+    pub fatigue_curve: Curve,
+    /// Seconds of rest that (nearly) undo fatigue; rest cools down the
+    /// warmup at the same rate
+    pub recovery_secs: f64,
+}
+
+impl Default for FatigueModel {
+    fn default() -> Self {
+        Self {
+            warmup_slowdown: 0.35,
+            warmup_secs: 15.0,
+            warmup_curve: Curve::Exponential,
+            fatigue_slowdown: 0.2,
+            fatigue_secs: 5400.0,
+            fatigue_curve: Curve::Sigmoid,
+            recovery_secs: 600.0,
+        }
+    }
+}
+
+/// Warmth and fatigue built up so far in a session
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FatigueState {
+    /// Effective seconds of warming up
+    warm_secs: f64,
+    /// Effective seconds of tiring effort
+    effort_secs: f64,
+}
+
+impl FatigueState {
+    /// Account for time spent typing
+    pub fn advance(&mut self, typed: Duration) {
+        let secs = typed.as_secs_f64();
+        self.warm_secs += secs;
+        self.effort_secs += secs;
+    }
+
+    /// Account for a rest: fatigue wears off and warmth fades, both by about
+    /// 95% over the model's recovery time
+    pub fn rest(&mut self, rested: Duration, model: &FatigueModel) {
+        if model.recovery_secs <= 0.0 {
+            return;
+        }
+        let decay = (-3.0 * rested.as_secs_f64() / model.recovery_secs).exp();
+        self.warm_secs *= decay;
+        self.effort_secs *= decay;
+    }
+
+    /// Delay multiplier for warming up (1 once warm)
+    pub fn warmup_factor(&self, model: &FatigueModel) -> f64 {
+        if model.warmup_slowdown <= 0.0 || model.warmup_secs <= 0.0 {
+            return 1.0;
+        }
+        let warmth = model.warmup_curve.at(self.warm_secs / model.warmup_secs);
+        1.0 + model.warmup_slowdown * (1.0 - warmth)
+    }
+
+    /// Delay multiplier for fatigue (1 when fresh)
+    pub fn fatigue_factor(&self, model: &FatigueModel) -> f64 {
+        if model.fatigue_slowdown <= 0.0 || model.fatigue_secs <= 0.0 {
+            return 1.0;
+        }
+        1.0 + model.fatigue_slowdown * model.fatigue_curve.at(self.effort_secs / model.fatigue_secs)
+    }
+}
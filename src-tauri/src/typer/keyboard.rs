@@ -1,17 +1,108 @@
-use enigo::{Enigo, Keyboard, Settings};
+use enigo::{Direction, Enigo, InputError, Key, Keyboard, Settings};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// A keyboard shortcut such as "Ctrl+B" or "Ctrl+Alt+1"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyCombo {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// Cmd on macOS, the Windows key elsewhere
+    pub meta: bool,
+    pub key: char,
+}
+
+impl KeyCombo {
+    /// Parse "Ctrl+Shift+K"-style combos. Modifier names are
+    /// case-insensitive; the key is a single character.
+    pub fn parse(combo: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid shortcut '{}'", combo);
+        let mut keys = Self {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            meta: false,
+            key: ' ',
+        };
+        let mut parts: Vec<&str> = combo.split('+').map(str::trim).collect();
+        // "Ctrl++" binds the plus key
+        if combo.trim_end().ends_with("++") {
+            parts.pop();
+            parts.pop();
+            parts.push("+");
+        }
+        let (key, modifiers) = parts.split_last().ok_or_else(invalid)?;
+        for modifier in modifiers {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => keys.ctrl = true,
+                "alt" | "option" => keys.alt = true,
+                "shift" => keys.shift = true,
+                "cmd" | "command" | "meta" | "super" | "win" => keys.meta = true,
+                _ => return Err(invalid()),
+            }
+        }
+        let mut chars = key.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => keys.key = c.to_ascii_lowercase(),
+            _ => return Err(invalid()),
+        }
+        Ok(keys)
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [(self.ctrl, "Ctrl"), (self.alt, "Alt"), (self.shift, "Shift"), (self.meta, "Cmd")] {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", self.key.to_ascii_uppercase())
+    }
+}
+
+impl TryFrom<String> for KeyCombo {
+    type Error = String;
+
+    fn try_from(combo: String) -> Result<Self, String> {
+        Self::parse(&combo)
+    }
+}
+
+impl From<KeyCombo> for String {
+    fn from(combo: KeyCombo) -> Self {
+        combo.to_string()
+    }
+}
 
 /// Wrapper around enigo for keyboard simulation
 pub struct KeyboardSimulator {
     enigo: Enigo,
+    /// Keys currently held down, with when to release them
+    held: Vec<(Key, Instant)>,
+}
+
+/// Key that produces `c`
+fn key_for(c: char) -> Key {
+    match c {
+        '\n' => Key::Return,
+        '\t' => Key::Tab,
+        _ => Key::Unicode(c),
+    }
 }
 
 impl KeyboardSimulator {
     pub fn new() -> Result<Self, String> {
         let enigo = Enigo::new(&Settings::default())
             .map_err(|e| format!("Failed to create keyboard simulator: {}", e))?;
-        Ok(Self { enigo })
+        Ok(Self {
+            enigo,
+            held: Vec::new(),
+        })
     }
     
     /// Type a single character
@@ -30,12 +121,94 @@ impl KeyboardSimulator {
     
     /// Press backspace
     pub fn backspace(&mut self) -> Result<(), String> {
-        use enigo::Key;
         self.enigo
-            .key(Key::Backspace, enigo::Direction::Click)
+            .key(Key::Backspace, Direction::Click)
             .map_err(|e| format!("Failed to press backspace: {}", e))
     }
     
+    /// Press the key for `c` and keep it down for `hold`. It comes up in
+    /// [`Self::release_due`], so the next key can go down first (rollover).
+    /// Characters the backend can't press as a key are typed in one go.
+    pub fn press_char(&mut self, c: char, hold: Duration) -> Result<(), String> {
+        if self.press(key_for(c), hold).is_err() {
+            return self.type_char(c);
+        }
+        Ok(())
+    }
+
+    /// Press backspace and keep it down for `hold`
+    pub fn press_backspace(&mut self, hold: Duration) -> Result<(), String> {
+        self.press(Key::Backspace, hold)
+            .map_err(|e| format!("Failed to press backspace: {}", e))
+    }
+
+    fn press(&mut self, key: Key, hold: Duration) -> Result<(), InputError> {
+        // A key still down from its previous press has to come up first
+        if let Some(pos) = self.held.iter().position(|(k, _)| *k == key) {
+            self.held.remove(pos);
+            self.enigo.key(key, Direction::Release)?;
+        }
+        self.enigo.key(key, Direction::Press)?;
+        self.held.push((key, Instant::now() + hold));
+        Ok(())
+    }
+
+    /// Release held keys whose hold time is over
+    pub fn release_due(&mut self) -> Result<(), String> {
+        let now = Instant::now();
+        while let Some(pos) = self.held.iter().position(|(_, at)| *at <= now) {
+            let (key, _) = self.held.remove(pos);
+            self.release(key)?;
+        }
+        Ok(())
+    }
+
+    /// Release every held key
+    pub fn release_all(&mut self) -> Result<(), String> {
+        while let Some((key, _)) = self.held.pop() {
+            self.release(key)?;
+        }
+        Ok(())
+    }
+
+    /// When the next held key is due to come up
+    pub fn next_release(&self) -> Option<Instant> {
+        self.held.iter().map(|(_, at)| *at).min()
+    }
+
+    fn release(&mut self, key: Key) -> Result<(), String> {
+        self.enigo
+            .key(key, Direction::Release)
+            .map_err(|e| format!("Failed to release {:?}: {}", key, e))
+    }
+
+    /// Press a shortcut: modifiers down, key, modifiers up
+    pub fn shortcut(&mut self, combo: KeyCombo) -> Result<(), String> {
+        self.release_all()?;
+        let modifiers: Vec<Key> = [
+            (combo.ctrl, Key::Control),
+            (combo.alt, Key::Alt),
+            (combo.shift, Key::Shift),
+            (combo.meta, Key::Meta),
+        ]
+        .into_iter()
+        .filter_map(|(held, key)| held.then_some(key))
+        .collect();
+
+        let press = |enigo: &mut Enigo| -> Result<(), InputError> {
+            for key in &modifiers {
+                enigo.key(*key, Direction::Press)?;
+            }
+            enigo.key(key_for(combo.key), Direction::Click)
+        };
+        let pressed = press(&mut self.enigo);
+        // Always let go of the modifiers, even if the key failed
+        for key in modifiers.iter().rev() {
+            let _ = self.enigo.key(*key, Direction::Release);
+        }
+        pressed.map_err(|e| format!("Failed to press {}: {}", combo, e))
+    }
+
     /// Press backspace multiple times
     pub fn backspace_n(&mut self, n: usize, delay_ms: u64) -> Result<(), String> {
         for _ in 0..n {
@@ -48,6 +221,13 @@ impl KeyboardSimulator {
     }
 }
 
+impl Drop for KeyboardSimulator {
+    /// Never leave a key stuck down
+    fn drop(&mut self) {
+        let _ = self.release_all();
+    }
+}
+
 impl Default for KeyboardSimulator {
     fn default() -> Self {
         Self::new().expect("Failed to create default KeyboardSimulator")
@@ -1,4 +1,5 @@
 use once_cell::sync::Lazy;
+use serde::Serialize;
 use std::collections::HashMap;
 
 /// QWERTY keyboard neighbor map for adjacent key mistakes
@@ -53,7 +54,8 @@ pub static NEIGHBORS: Lazy<HashMap<char, Vec<char>>> = Lazy::new(|| {
 });
 
 /// Mistake types
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum MistakeType {
     /// Type an adjacent key instead
     AdjacentKey,
@@ -68,6 +70,16 @@ pub enum MistakeType {
 }
 
 impl MistakeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AdjacentKey => "adjacent_key",
+            Self::Transposition => "transposition",
+            Self::Omission => "omission",
+            Self::DoubleTap => "double_tap",
+            Self::Capitalization => "capitalization",
+        }
+    }
+
     /// Get a random mistake type with weighted probabilities
     pub fn random() -> Self {
         use rand::Rng;
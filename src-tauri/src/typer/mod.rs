@@ -1,18 +1,94 @@
+pub mod breaks;
+pub mod calibrate;
+pub mod code;
+pub mod distribution;
+pub mod events;
+pub mod fatigue;
 pub mod keyboard;
 pub mod mistakes;
+pub mod ngrams;
+pub mod plan;
+pub mod queue;
+pub mod stats;
+pub mod stream;
+pub mod structure;
 pub mod timing;
+pub mod trigger;
+pub mod window;
+pub mod words;
 
+use breaks::{BreakSchedule, BreakTimer};
 use crate::config::{Config, TypingProgress, TypingStatus};
+use crate::content::{template, Document};
+use events::{EngineEvent, EventSink};
+use fatigue::FatigueState;
 use keyboard::KeyboardSimulator;
-use mistakes::generate_mistake;
 use parking_lot::Mutex;
-use rand::Rng;
-use std::sync::atomic::{AtomicBool, Ordering};
+use plan::{Action, Planner};
+use queue::{ContentQueue, QueueItem};
+use stats::{KeystrokeRecord, SessionRecorder, SessionReport};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicIsize, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter};
-use tokio::sync::watch;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, watch};
 use tokio::time::sleep;
+use trigger::StartTrigger;
+use window::{ActiveWindow, WindowProbe};
+
+/// WPM change per speed up/down step
+pub const SPEED_STEP_WPM: u32 = 5;
+/// Bounds for speed adjustments
+pub const MIN_WPM: u32 = 10;
+pub const MAX_WPM: u32 = 250;
+
+/// Backspace delay when undoing a run
+const UNDO_BACKSPACE_MS: u64 = 5;
+/// How often sleeps check for a stop request
+const STOP_POLL: Duration = Duration::from_millis(25);
+/// How often the focused window is checked
+const WINDOW_POLL: Duration = Duration::from_millis(250);
+/// How often the focus guard checks the target window while typing
+const FOCUS_POLL: Duration = Duration::from_millis(200);
+
+/// Errors that end a typing run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypingError {
+    /// Nothing has been loaded
+    NoContent,
+    /// The loaded content has no characters
+    EmptyContent,
+    /// The keyboard backend failed
+    Keyboard(String),
+    /// The start trigger cannot be used (e.g. no window detection)
+    Trigger(String),
+    /// The background typing task panicked or was cancelled
+    Task(String),
+    /// A template variable has no value
+    Template(String),
+}
+
+impl fmt::Display for TypingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoContent => write!(f, "No content to type"),
+            Self::EmptyContent => write!(f, "Content is empty"),
+            Self::Keyboard(e) => write!(f, "{}", e),
+            Self::Trigger(e) => write!(f, "{}", e),
+            Self::Task(e) => write!(f, "Typing task failed: {}", e),
+            Self::Template(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for TypingError {}
+
+impl From<TypingError> for String {
+    fn from(e: TypingError) -> Self {
+        e.to_string()
+    }
+}
 
 /// Shared state for the typing engine
 pub struct TypingEngine {
@@ -20,18 +96,56 @@ pub struct TypingEngine {
     status: Mutex<TypingStatus>,
     /// Current configuration
     config: Mutex<Config>,
-    /// Content to type
-    content: Mutex<Option<String>>,
-    /// File name
-    file_name: Mutex<Option<String>>,
+    /// Bumped on every config change so a running session can pick it up
+    config_version: AtomicU64,
+    /// WPM added by speed up/down during the current run, kept when a queue
+    /// item switches to another profile
+    speed_nudge: AtomicI32,
+    /// Documents to type, in order
+    queue: Mutex<ContentQueue>,
+    /// A run has taken the queue and not finished yet
+    running: AtomicBool,
+    /// Index of the queue item being typed
+    current_item: Mutex<usize>,
+    /// Character counts of the items in the current run, after templates
+    /// are filled in
+    item_totals: Mutex<Vec<usize>>,
+    /// Values for template variables
+    variables: Mutex<HashMap<String, String>>,
     /// Current character index
     current_index: Mutex<usize>,
     /// Stop signal
     stop_signal: AtomicBool,
     /// Pause signal
     pause_signal: AtomicBool,
+    /// Skip-to-next-paragraph signal
+    skip_signal: AtomicBool,
+    /// Releases a run waiting on [`StartTrigger::Hotkey`]
+    trigger_signal: AtomicBool,
+    /// Backspace everything typed once the run stops
+    undo_signal: AtomicBool,
+    /// User accepted the newly focused window after a focus loss
+    focus_confirm: AtomicBool,
+    /// Ends a scheduled break early
+    break_end: AtomicBool,
+    /// Keys sent in the current run: characters typed minus backspaces. Not
+    /// clamped at zero, since a backspace can remove text the editor
+    /// inserted itself.
+    typed_chars: AtomicIsize,
     /// Pause watcher sender
     pause_tx: Mutex<Option<watch::Sender<bool>>>,
+    /// Fan-out of emitted events for local subscribers
+    event_tx: broadcast::Sender<EngineEvent>,
+    /// Statistics for the run in progress
+    session: Mutex<SessionRecorder>,
+    /// Report of the last finished run
+    last_report: Mutex<Option<SessionReport>>,
+    /// Keystroke log of the last finished run (if logging was enabled)
+    keystroke_log: Mutex<Vec<KeystrokeRecord>>,
+    /// Warmup and fatigue carried from one queue item to the next
+    fatigue: Mutex<FatigueState>,
+    /// Typing time since the last scheduled break
+    break_timer: Mutex<BreakTimer>,
 }
 
 impl Default for TypingEngine {
@@ -45,22 +159,53 @@ impl TypingEngine {
         Self {
             status: Mutex::new(TypingStatus::Idle),
             config: Mutex::new(Config::default()),
-            content: Mutex::new(None),
-            file_name: Mutex::new(None),
+            config_version: AtomicU64::new(0),
+            speed_nudge: AtomicI32::new(0),
+            queue: Mutex::new(ContentQueue::default()),
+            running: AtomicBool::new(false),
+            current_item: Mutex::new(0),
+            item_totals: Mutex::new(Vec::new()),
+            variables: Mutex::new(HashMap::new()),
             current_index: Mutex::new(0),
             stop_signal: AtomicBool::new(false),
             pause_signal: AtomicBool::new(false),
+            skip_signal: AtomicBool::new(false),
+            trigger_signal: AtomicBool::new(false),
+            undo_signal: AtomicBool::new(false),
+            focus_confirm: AtomicBool::new(false),
+            break_end: AtomicBool::new(false),
+            typed_chars: AtomicIsize::new(0),
             pause_tx: Mutex::new(None),
+            event_tx: broadcast::channel(256).0,
+            session: Mutex::new(SessionRecorder::default()),
+            last_report: Mutex::new(None),
+            keystroke_log: Mutex::new(Vec::new()),
+            fatigue: Mutex::new(FatigueState::default()),
+            break_timer: Mutex::new(BreakTimer::default()),
         }
     }
 
+    /// Emit an event to `events` and to every subscriber
+    pub fn emit(&self, events: &impl EventSink, event: &str, payload: serde_json::Value) {
+        let _ = self.event_tx.send(EngineEvent {
+            event: event.to_string(),
+            payload: payload.clone(),
+        });
+        events.emit_event(event, payload);
+    }
+
+    /// Receive every event the engine emits from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<EngineEvent> {
+        self.event_tx.subscribe()
+    }
+
     pub fn get_status(&self) -> TypingStatus {
         *self.status.lock()
     }
 
-    pub fn set_status(&self, status: TypingStatus, app: &AppHandle) {
+    pub fn set_status(&self, status: TypingStatus, events: &impl EventSink) {
         *self.status.lock() = status;
-        let _ = app.emit("typing-state-changed", serde_json::json!({ "status": status }));
+        self.emit(events, "typing-state-changed", serde_json::json!({ "status": status }));
     }
 
     pub fn get_config(&self) -> Config {
@@ -69,21 +214,128 @@ impl TypingEngine {
 
     pub fn set_config(&self, config: Config) {
         *self.config.lock() = config;
+        self.config_version.fetch_add(1, Ordering::SeqCst);
     }
 
-    pub fn set_content(&self, content: String, file_name: String) {
-        *self.content.lock() = Some(content);
-        *self.file_name.lock() = Some(file_name);
+    /// Replace the queue with a single document
+    /// Make `document` the only queued content. Refused once a run has
+    /// taken the queue, until it ends.
+    pub fn set_content(&self, document: Document) -> Result<(), String> {
+        let mut queue = self.queue.lock();
+        if self.running.load(Ordering::SeqCst) {
+            return Err("Cannot change the content while typing".to_string());
+        }
+        queue.replace(document);
+        drop(queue);
+        self.item_totals.lock().clear();
+        *self.current_item.lock() = 0;
         *self.current_index.lock() = 0;
+        Ok(())
+    }
+
+    /// Append a document to the queue
+    pub fn enqueue(
+        &self,
+        document: Document,
+        profile: Option<String>,
+        config: Option<Config>,
+        trigger: Option<StartTrigger>,
+    ) -> Result<QueueItem, String> {
+        self.queue
+            .lock()
+            .push(document, profile, config, trigger)
+            .cloned()
+    }
+
+    /// Queued documents
+    pub fn get_queue(&self) -> Vec<QueueItem> {
+        self.queue.lock().items().to_vec()
+    }
+
+    pub fn remove_queue_item(&self, id: u64) -> Result<(), String> {
+        self.queue.lock().remove(id)?;
+        self.item_totals.lock().clear();
+        Ok(())
+    }
+
+    pub fn move_queue_item(&self, id: u64, index: usize) -> Result<(), String> {
+        self.queue.lock().move_item(id, index)?;
+        self.item_totals.lock().clear();
+        Ok(())
+    }
+
+    pub fn clear_queue(&self) {
+        self.queue.lock().clear();
+        self.item_totals.lock().clear();
+        *self.current_item.lock() = 0;
+        *self.current_index.lock() = 0;
+    }
+
+    pub fn get_variables(&self) -> HashMap<String, String> {
+        self.variables.lock().clone()
+    }
+
+    /// Set the values template variables are filled in with
+    pub fn set_variables(&self, variables: HashMap<String, String>) {
+        *self.variables.lock() = variables;
+    }
+
+    /// Template variables of queued documents that have no value yet
+    pub fn missing_variables(&self) -> Vec<String> {
+        let base = self.get_config();
+        let values = self.variables.lock();
+        let mut missing: Vec<String> = Vec::new();
+        for item in self.queue.lock().items() {
+            if !item.config.as_ref().unwrap_or(&base).templates {
+                continue;
+            }
+            for name in template::missing(&item.variables, &values) {
+                if !missing.contains(&name) {
+                    missing.push(name);
+                }
+            }
+        }
+        missing
+    }
+
+    /// Queue items with their template variables filled in
+    fn render_items(&self, items: Vec<QueueItem>, base: &Config) -> Result<Vec<QueueItem>, TypingError> {
+        let values = self.get_variables();
+        items
+            .into_iter()
+            .map(|mut item| {
+                let config = item.config.as_ref().unwrap_or(base);
+                if config.templates {
+                    let max_mb = config.max_file_mb;
+                    item.content = template::render(&item.content, &values, &mut item.shortcuts, max_mb)
+                        .map_err(|e| TypingError::Template(format!("{}: {}", item.name, e)))?;
+                    item.char_count = item.content.chars().count();
+                }
+                Ok(item)
+            })
+            .collect()
+    }
+
+    /// Release a run waiting for the hotkey
+    pub fn trigger(&self) {
+        self.trigger_signal.store(true, Ordering::SeqCst);
     }
 
     pub fn get_file_name(&self) -> Option<String> {
-        self.file_name.lock().clone()
+        let index = *self.current_item.lock();
+        self.queue.lock().get(index).map(|item| item.name.clone())
     }
 
     pub fn get_progress(&self) -> TypingProgress {
-        let content = self.content.lock();
-        let total = content.as_ref().map(|c| c.len()).unwrap_or(0) as u32;
+        let item = *self.current_item.lock();
+        let (total, items) = {
+            let queue = self.queue.lock();
+            let total = match self.item_totals.lock().get(item) {
+                Some(&total) => total,
+                None => queue.get(item).map(|i| i.char_count).unwrap_or(0),
+            };
+            (total as u32, queue.len() as u32)
+        };
         let current = *self.current_index.lock() as u32;
         let percent = if total > 0 {
             (current as f32 / total as f32) * 100.0
@@ -94,6 +346,9 @@ impl TypingEngine {
             current,
             total,
             percent,
+            wpm: self.config.lock().base_wpm,
+            item: item as u32,
+            items,
         }
     }
 
@@ -108,17 +363,82 @@ impl TypingEngine {
         }
     }
 
+    /// Stop immediately, optionally backspacing everything typed in this run
+    pub fn abort(&self, undo: bool) {
+        self.undo_signal.store(undo, Ordering::SeqCst);
+        self.stop();
+    }
+
+    /// Net number of characters the current run has left in the target
+    pub fn typed_chars(&self) -> usize {
+        self.typed_chars.load(Ordering::SeqCst).max(0) as usize
+    }
+
+    /// Report of the last finished run
+    pub fn session_report(&self) -> Option<SessionReport> {
+        self.last_report.lock().clone()
+    }
+
+    /// Keystroke log of the last finished run
+    pub fn keystroke_log(&self) -> Vec<KeystrokeRecord> {
+        self.keystroke_log.lock().clone()
+    }
+
+    /// Sleep for `ms`, waking early if the run is stopped and releasing held
+    /// keys as their hold time runs out
+    fn wait_keys(&self, keyboard: &mut KeyboardSimulator, ms: u64) -> Result<(), String> {
+        let deadline = Instant::now() + Duration::from_millis(ms);
+        loop {
+            keyboard.release_due()?;
+            let now = Instant::now();
+            if now >= deadline || self.is_stopped() {
+                return Ok(());
+            }
+            let until = keyboard.next_release().map_or(deadline, |at| at.min(deadline));
+            std::thread::sleep(until.saturating_duration_since(now).min(STOP_POLL));
+        }
+    }
+
     pub fn pause(&self) {
         self.pause_signal.store(true, Ordering::SeqCst);
     }
 
+    /// Resume after a pause. After a focus loss this also confirms that
+    /// typing should continue into the window that now has focus; during a
+    /// scheduled break it ends the break early.
     pub fn resume(&self) {
         self.pause_signal.store(false, Ordering::SeqCst);
+        self.focus_confirm.store(true, Ordering::SeqCst);
+        self.break_end.store(true, Ordering::SeqCst);
         if let Some(tx) = self.pause_tx.lock().as_ref() {
             let _ = tx.send(false);
         }
     }
 
+    /// Skip the rest of the current paragraph
+    pub fn skip_paragraph(&self) {
+        self.skip_signal.store(true, Ordering::SeqCst);
+    }
+
+    /// Change the base speed by `steps` increments of [`SPEED_STEP_WPM`],
+    /// returning the new WPM
+    pub fn adjust_speed(&self, steps: i32) -> u32 {
+        let mut config = self.config.lock();
+        let wpm = config.base_wpm as i32 + steps * SPEED_STEP_WPM as i32;
+        let wpm = wpm.clamp(MIN_WPM as i32, MAX_WPM as i32);
+        self.speed_nudge.fetch_add(wpm - config.base_wpm as i32, Ordering::SeqCst);
+        config.base_wpm = wpm as u32;
+        self.config_version.fetch_add(1, Ordering::SeqCst);
+        config.base_wpm
+    }
+
+    /// `config` with this run's speed nudge applied
+    fn nudged(&self, mut config: Config) -> Config {
+        let wpm = config.base_wpm as i32 + self.speed_nudge.load(Ordering::SeqCst);
+        config.base_wpm = wpm.clamp(MIN_WPM as i32, MAX_WPM as i32) as u32;
+        config
+    }
+
     pub fn is_stopped(&self) -> bool {
         self.stop_signal.load(Ordering::SeqCst)
     }
@@ -127,146 +447,599 @@ impl TypingEngine {
         self.pause_signal.load(Ordering::SeqCst)
     }
 
-    /// Run the typing simulation
-    pub async fn run(self: Arc<Self>, app: AppHandle) -> Result<(), String> {
+    /// Run the typing simulation over every queued document
+    pub async fn run<S: EventSink + Clone>(self: Arc<Self>, events: S) -> Result<(), TypingError> {
         // Reset signals
         self.stop_signal.store(false, Ordering::SeqCst);
         self.pause_signal.store(false, Ordering::SeqCst);
+        self.skip_signal.store(false, Ordering::SeqCst);
+        self.trigger_signal.store(false, Ordering::SeqCst);
+        self.undo_signal.store(false, Ordering::SeqCst);
+        self.typed_chars.store(0, Ordering::SeqCst);
+        self.speed_nudge.store(0, Ordering::SeqCst);
+        *self.current_item.lock() = 0;
         *self.current_index.lock() = 0;
+        *self.fatigue.lock() = FatigueState::default();
+        self.break_timer.lock().reset();
 
         // Create pause watcher
         let (pause_tx, _pause_rx) = watch::channel(false);
         *self.pause_tx.lock() = Some(pause_tx);
 
-        // Get content
-        let content = {
-            let guard = self.content.lock();
-            guard.clone().ok_or("No content to type")?
-        };
-        let chars: Vec<char> = content.chars().collect();
-        let total_chars = chars.len();
+        // Get content. From here until the run ends the content cannot be
+        // replaced, even before the status shows the run.
+        self.running.store(true, Ordering::SeqCst);
+        let _running = ClearOnDrop(&self.running);
+        let items = self.get_queue();
+        if items.is_empty() {
+            return Err(TypingError::NoContent);
+        }
+        if items.iter().all(|item| item.char_count == 0) {
+            return Err(TypingError::EmptyContent);
+        }
+        let base_config = self.get_config();
+        let items = self.render_items(items, &base_config)?;
+        *self.item_totals.lock() = items.iter().map(|item| item.char_count).collect();
 
-        if total_chars == 0 {
-            return Err("Content is empty".to_string());
+        *self.session.lock() = SessionRecorder::start(base_config.keystroke_log);
+        let mut result = self.clone().run_items(&items, &base_config, &events).await;
+
+        let (report, log) = std::mem::take(&mut *self.session.lock()).finish(self.is_stopped());
+        *self.last_report.lock() = Some(report.clone());
+        *self.keystroke_log.lock() = log;
+        self.emit(&events, "session-report", serde_json::to_value(report).unwrap_or_default());
+
+        if result.is_ok() && self.is_stopped() && self.undo_signal.swap(false, Ordering::SeqCst) {
+            result = self.clone().undo(&events).await;
+        }
+        // Put back the config the profiles replaced, keeping speed nudges
+        if items.iter().any(|item| item.config.is_some()) {
+            self.set_config(self.nudged(base_config));
+            self.emit_config(&events);
+        }
+
+        if let Err(e) = result {
+            self.set_status(TypingStatus::Error, &events);
+            self.emit(&events, "typing-error", serde_json::json!({ "message": e.to_string() }));
+            return Err(e);
         }
 
-        // Countdown
-        let countdown_secs = self.config.lock().countdown_seconds;
-        self.set_status(TypingStatus::Countdown, &app);
+        // Done
+        if !self.is_stopped() {
+            self.set_status(TypingStatus::Done, &events);
+        } else {
+            self.set_status(TypingStatus::Ready, &events);
+        }
+
+        Ok(())
+    }
 
-        for i in (1..=countdown_secs).rev() {
+    fn emit_config(&self, events: &impl EventSink) {
+        self.emit(
+            events,
+            "config-changed",
+            serde_json::to_value(self.get_config()).unwrap_or_default(),
+        );
+    }
+
+    async fn run_items<S: EventSink + Clone>(
+        self: Arc<Self>,
+        items: &[QueueItem],
+        base_config: &Config,
+        events: &S,
+    ) -> Result<(), TypingError> {
+        let profiled = items.iter().any(|item| item.config.is_some());
+        for (index, item) in items.iter().enumerate() {
+            if item.char_count == 0 {
+                continue;
+            }
+
+            *self.current_item.lock() = index;
+            *self.current_index.lock() = 0;
+
+            // Items with their own profile temporarily replace the active
+            // config; the others type with the config the run started with
+            if profiled {
+                let config = item.config.clone().unwrap_or_else(|| base_config.clone());
+                self.set_config(self.nudged(config));
+                self.emit_config(events);
+            }
+
+            let trigger = item
+                .trigger
+                .clone()
+                .unwrap_or_else(|| self.config.lock().start_trigger.clone());
+            let waiting = Instant::now();
+            if !self.wait_for_trigger(&trigger, events).await? {
+                return Ok(());
+            }
+            if index > 0 {
+                let config = self.get_config();
+                self.fatigue.lock().rest(waiting.elapsed(), &config.fatigue);
+            }
+
+            self.emit(
+                events,
+                "queue-item-started",
+                serde_json::json!({ "id": item.id, "index": index, "name": item.name }),
+            );
+            self.set_status(TypingStatus::Typing, events);
+
+            self.clone().type_content(item, events.clone()).await?;
             if self.is_stopped() {
-                self.set_status(TypingStatus::Ready, &app);
                 return Ok(());
             }
-            let _ = app.emit("countdown-tick", serde_json::json!({ "remaining": i }));
-            sleep(Duration::from_secs(1)).await;
+
+            self.emit(
+                events,
+                "queue-item-finished",
+                serde_json::json!({ "id": item.id, "index": index, "name": item.name }),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Wait until `trigger` fires; `false` if the run was stopped meanwhile
+    async fn wait_for_trigger(
+        &self,
+        trigger: &StartTrigger,
+        events: &impl EventSink,
+    ) -> Result<bool, TypingError> {
+        let trigger_json = serde_json::to_value(trigger).unwrap_or_default();
+
+        if let StartTrigger::Countdown { seconds } = trigger {
+            let countdown_secs = seconds.unwrap_or(self.config.lock().countdown_seconds);
+            self.set_status(TypingStatus::Countdown, events);
+
+            for i in (1..=countdown_secs).rev() {
+                if self.is_stopped() {
+                    return Ok(false);
+                }
+                self.emit(events, "countdown-tick", serde_json::json!({ "remaining": i }));
+                sleep(Duration::from_secs(1)).await;
+            }
+            return Ok(!self.is_stopped());
+        }
+
+        // The other triggers wait in `Waiting`; the start/stop hotkey or
+        // `continue_queue` always fires them early
+        let probe = match trigger {
+            StartTrigger::WindowFocus { .. } => {
+                Some(WindowProbe::new().map_err(TypingError::Trigger)?)
+            }
+            _ => None,
+        };
+        self.trigger_signal.store(false, Ordering::SeqCst);
+        self.set_status(TypingStatus::Waiting, events);
+        self.emit(events, "trigger-waiting", serde_json::json!({ "trigger": trigger_json }));
+
+        let mut last_tick = None;
+        let mut next_probe = Instant::now();
+        while !self.trigger_signal.swap(false, Ordering::SeqCst) {
+            if self.is_stopped() {
+                return Ok(false);
+            }
+            match trigger {
+                StartTrigger::WindowFocus { title } if Instant::now() >= next_probe => {
+                    next_probe = Instant::now() + WINDOW_POLL;
+                    let focused = probe
+                        .as_ref()
+                        .and_then(|p| p.active_window())
+                        .is_some_and(|w| StartTrigger::title_matches(title, &w.title));
+                    if focused {
+                        break;
+                    }
+                }
+                StartTrigger::At { unix_ms } => {
+                    let remaining = trigger::millis_until(*unix_ms);
+                    if remaining == 0 {
+                        break;
+                    }
+                    let secs = remaining.div_ceil(1000);
+                    if last_tick != Some(secs) {
+                        self.emit(events, "trigger-tick", serde_json::json!({ "remaining": secs }));
+                        last_tick = Some(secs);
+                    }
+                }
+                _ => {}
+            }
+            sleep(STOP_POLL).await;
+        }
+
+        if self.is_stopped() {
+            return Ok(false);
+        }
+        self.emit(events, "trigger-fired", serde_json::json!({ "trigger": trigger_json }));
+        Ok(true)
+    }
+
+    /// Backspace everything typed in this run
+    async fn undo<S: EventSink + Clone>(self: Arc<Self>, events: &S) -> Result<(), TypingError> {
+        let count = self.typed_chars.swap(0, Ordering::SeqCst).max(0) as usize;
+        if count == 0 {
+            return Ok(());
+        }
+
+        tokio::task::spawn_blocking(move || {
+            let mut keyboard = KeyboardSimulator::new()?;
+            keyboard.backspace_n(count, UNDO_BACKSPACE_MS)
+        })
+        .await
+        .map_err(|e| TypingError::Task(e.to_string()))?
+        .map_err(TypingError::Keyboard)?;
+
+        self.emit(events, "typing-undone", serde_json::json!({ "chars": count }));
+        Ok(())
+    }
+
+    /// Start guarding the focused window. If the platform has no window
+    /// detection, report `focus-guard-unavailable` and clear `available` so
+    /// the run does not try again.
+    fn start_focus_guard(&self, available: &mut bool, events: &impl EventSink) -> Option<FocusGuard> {
+        if !*available {
+            return None;
+        }
+        match FocusGuard::start() {
+            Ok(guard) => Some(guard),
+            Err(e) => {
+                *available = false;
+                self.emit(events, "focus-guard-unavailable", serde_json::json!({ "message": e }));
+                None
+            }
         }
+    }
 
-        // Start typing
-        self.set_status(TypingStatus::Typing, &app);
+    /// Check the focus guard; if the target window lost focus, pause until it
+    /// is refocused or the user resumes (which retargets the guard to the
+    /// window that now has focus). `false` if the run was stopped.
+    fn guard_focus(&self, guard: &mut FocusGuard, events: &impl EventSink) -> bool {
+        let Some(current) = guard.poll() else {
+            return true;
+        };
+
+        let lost_at = Instant::now();
+        self.focus_confirm.store(false, Ordering::SeqCst);
+        self.set_status(TypingStatus::FocusLost, events);
+        self.emit(
+            events,
+            "focus-lost",
+            serde_json::json!({ "expected": guard.target, "actual": current }),
+        );
+
+        loop {
+            std::thread::sleep(FOCUS_POLL);
+            if self.is_stopped() {
+                self.session.lock().add_paused_time(lost_at.elapsed());
+                return false;
+            }
+
+            let confirmed = self.focus_confirm.swap(false, Ordering::SeqCst);
+            let refocused = guard.is_focused();
+            if confirmed || refocused {
+                self.session.lock().add_paused_time(lost_at.elapsed());
+                if confirmed {
+                    guard.retarget();
+                }
+                let status = if self.is_paused() {
+                    TypingStatus::Paused
+                } else {
+                    TypingStatus::Typing
+                };
+                self.set_status(status, events);
+                self.emit(
+                    events,
+                    "focus-restored",
+                    serde_json::json!({ "window": guard.target, "confirmed": confirmed }),
+                );
+                return true;
+            }
+        }
+    }
+
+    /// Sit out a scheduled break of `duration`. Resuming ends it early.
+    /// Returns how long the break lasted, or `None` if the run was stopped.
+    fn take_break(
+        &self,
+        duration: Duration,
+        schedule: &BreakSchedule,
+        events: &impl EventSink,
+    ) -> Option<Duration> {
+        let started = Instant::now();
+        self.break_end.store(false, Ordering::SeqCst);
+        if schedule.show_status {
+            self.set_status(TypingStatus::OnBreak, events);
+        }
+        self.emit(
+            events,
+            "break-started",
+            serde_json::json!({ "durationMs": duration.as_millis() as u64 }),
+        );
+
+        let mut ended_early = false;
+        while started.elapsed() < duration {
+            std::thread::sleep(STOP_POLL);
+            if self.is_stopped() {
+                self.session.lock().add_break(started.elapsed());
+                return None;
+            }
+            if self.break_end.swap(false, Ordering::SeqCst) {
+                ended_early = true;
+                break;
+            }
+        }
+
+        let taken = started.elapsed();
+        self.session.lock().add_break(taken);
+        if schedule.show_status {
+            let status = if self.is_paused() {
+                TypingStatus::Paused
+            } else {
+                TypingStatus::Typing
+            };
+            self.set_status(status, events);
+        }
+        self.emit(
+            events,
+            "break-ended",
+            serde_json::json!({ "durationMs": taken.as_millis() as u64, "endedEarly": ended_early }),
+        );
+        Some(taken)
+    }
+
+    /// Type one document with the keyboard backend
+    async fn type_content<S: EventSink + Clone>(
+        self: Arc<Self>,
+        item: &QueueItem,
+        events: S,
+    ) -> Result<(), TypingError> {
+        let mut planner =
+            Planner::new(item.content.clone(), item.code_language).with_shortcuts(&item.shortcuts);
+        planner.set_fatigue(*self.fatigue.lock());
 
         // Create keyboard simulator in a blocking context
         let engine = self.clone();
-        let app_clone = app.clone();
-        
+
+        let item = *self.current_item.lock();
+
         let result = tokio::task::spawn_blocking(move || {
             let mut keyboard = KeyboardSimulator::new()?;
-            let config = engine.config.lock().clone();
-            let mut rng = rand::thread_rng();
-            
-            let mut i = 0;
-            
-            while i < total_chars {
+            let typing_started = Instant::now();
+            let mut config = engine.get_config();
+            let mut config_version = engine.config_version.load(Ordering::SeqCst);
+
+            // Remember the window we start typing into
+            let mut focus_available = true;
+            let mut guard = if config.focus_guard {
+                engine.start_focus_guard(&mut focus_available, &events)
+            } else {
+                None
+            };
+
+            'typing: loop {
                 // Check stop signal
-                if engine.stop_signal.load(Ordering::SeqCst) {
-                    return Ok::<(), String>(());
+                if engine.is_stopped() {
+                    break;
                 }
-                
+
                 // Check pause signal
+                let paused_at = Instant::now();
+                if engine.pause_signal.load(Ordering::SeqCst) {
+                    keyboard.release_all()?;
+                }
                 while engine.pause_signal.load(Ordering::SeqCst) {
                     std::thread::sleep(Duration::from_millis(100));
-                    if engine.stop_signal.load(Ordering::SeqCst) {
-                        return Ok(());
+                    if engine.is_stopped() {
+                        break 'typing;
                     }
                 }
-                
-                let current_char = chars[i];
-                let next_char = chars.get(i + 1).copied();
-
-                // Calculate delay using enhanced word-aware timing
-                let delay = timing::calculate_delay_v2(
-                    &config,
-                    &chars,
-                    i,
-                    total_chars,
-                );
-                
-                // Maybe generate a mistake
-                let mistake_result = generate_mistake(
-                    current_char,
-                    next_char,
-                    config.mistake_rate,
-                );
-                
-                // Type the character(s)
-                for c in &mistake_result.chars_to_type {
-                    keyboard.type_char(*c)?;
-                    std::thread::sleep(Duration::from_millis(delay / 2));
+                engine.session.lock().add_paused_time(paused_at.elapsed());
+
+                // Don't type into a window the user switched to
+                if let Some(guard) = guard.as_mut() {
+                    if !engine.guard_focus(guard, &events) {
+                        break;
+                    }
+                }
+
+                // Pauses and focus losses are rest
+                planner.rest(paused_at.elapsed(), &config);
+
+                // Take a scheduled break once enough typing time has passed
+                let due = engine
+                    .break_timer
+                    .lock()
+                    .due(&config.breaks, planner.at_paragraph_start());
+                if let Some(duration) = due {
+                    keyboard.release_all()?;
+                    let Some(taken) = engine.take_break(duration, &config.breaks, &events) else {
+                        break;
+                    };
+                    planner.rest(taken, &config);
+                    engine.break_timer.lock().reset();
+                }
+
+                // Check skip signal
+                if engine.skip_signal.swap(false, Ordering::SeqCst) {
+                    planner.skip_paragraph();
                 }
-                
-                // If a mistake was made, maybe correct it
-                if mistake_result.mistake_made {
-                    let should_correct = rng.gen::<f64>() < config.correction_rate;
-                    
-                    if should_correct {
-                        // Wait before noticing mistake
-                        std::thread::sleep(Duration::from_millis(timing::notice_mistake_delay()));
-                        
-                        // Backspace to remove wrong chars
-                        let backspace_delay = timing::backspace_delay(&config);
-                        keyboard.backspace_n(mistake_result.chars_to_type.len(), backspace_delay)?;
-                        
-                        // Type correctly
-                        for j in 0..mistake_result.chars_consumed {
-                            if i + j < total_chars {
-                                keyboard.type_char(chars[i + j])?;
-                                std::thread::sleep(Duration::from_millis(delay));
-                            }
-                        }
+
+                // Pick up config changes made while typing
+                let version = engine.config_version.load(Ordering::SeqCst);
+                if version != config_version {
+                    config = engine.get_config();
+                    config_version = version;
+                    if !config.focus_guard {
+                        guard = None;
+                    } else if guard.is_none() {
+                        guard = engine.start_focus_guard(&mut focus_available, &events);
                     }
                 }
-                
-                // Move forward
-                i += mistake_result.chars_consumed;
-                
+
+                let start_index = planner.index();
+                let Some(chunk) = planner.next_chunk(&config) else {
+                    break;
+                };
+
+                // Type the chunk
+                let chunk_started = Instant::now();
+                for step in &chunk.steps {
+                    if engine.is_stopped() {
+                        break 'typing;
+                    }
+                    engine
+                        .session
+                        .lock()
+                        .keystroke(item, start_index, &chunk, step);
+                    let hold = Duration::from_millis(step.hold_ms);
+                    match step.action {
+                        Action::Type(c) if step.hold_ms > 0 => keyboard.press_char(c, hold)?,
+                        Action::Type(c) => keyboard.type_char(c)?,
+                        Action::Backspace if step.hold_ms > 0 => keyboard.press_backspace(hold)?,
+                        Action::Backspace => keyboard.backspace()?,
+                        Action::Shortcut(keys) => keyboard.shortcut(keys)?,
+                        Action::Wait => {}
+                    }
+                    engine.typed_chars.fetch_add(step.action.net_chars(), Ordering::SeqCst);
+                    engine.wait_keys(&mut keyboard, step.delay_ms)?;
+                }
+
+                engine
+                    .session
+                    .lock()
+                    .chunk(&chunk, chunk.end_index - start_index);
+
                 // Update progress
-                *engine.current_index.lock() = i;
+                *engine.current_index.lock() = chunk.end_index;
                 let progress = engine.get_progress();
-                let _ = app_clone.emit("typing-progress", progress);
-                
+                engine.emit(
+                    &events,
+                    "typing-progress",
+                    serde_json::to_value(progress).unwrap_or_default(),
+                );
+
                 // Wait
-                std::thread::sleep(Duration::from_millis(delay));
+                engine.wait_keys(&mut keyboard, chunk.pause_ms)?;
+                engine.break_timer.lock().add(chunk_started.elapsed());
             }
-            
-            Ok(())
+
+            engine.session.lock().add_typing_time(typing_started.elapsed());
+            *engine.fatigue.lock() = planner.fatigue();
+            Ok::<(), String>(())
         })
         .await
-        .map_err(|e| format!("Typing task failed: {}", e))?;
+        .map_err(|e| TypingError::Task(e.to_string()))?;
 
-        if let Err(e) = result {
-            self.set_status(TypingStatus::Error, &app);
-            let _ = app.emit("typing-error", serde_json::json!({ "message": e }));
-            return Err(e);
+        result.map_err(TypingError::Keyboard)
+    }
+}
+
+/// Clears a flag when dropped, however the function holding it returns
+struct ClearOnDrop<'a>(&'a AtomicBool);
+
+impl Drop for ClearOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Tracks the window typing started in
+struct FocusGuard {
+    probe: WindowProbe,
+    target: Option<ActiveWindow>,
+    last_check: Instant,
+}
+
+impl FocusGuard {
+    /// Record the focused window; fails if window detection is unavailable
+    fn start() -> Result<Self, String> {
+        let probe = WindowProbe::new()?;
+        let target = probe.active_window();
+        Ok(Self {
+            probe,
+            target,
+            last_check: Instant::now(),
+        })
+    }
+
+    /// The newly focused window if focus moved away from the target. Checks at
+    /// most every [`FOCUS_POLL`].
+    fn poll(&mut self) -> Option<ActiveWindow> {
+        if self.last_check.elapsed() < FOCUS_POLL {
+            return None;
         }
+        self.last_check = Instant::now();
 
-        // Done
-        if !self.is_stopped() {
-            self.set_status(TypingStatus::Done, &app);
-        } else {
-            self.set_status(TypingStatus::Ready, &app);
+        let current = self.probe.active_window()?;
+        match &self.target {
+            Some(target) if target.id != current.id => Some(current),
+            Some(_) => None,
+            None => {
+                self.target = Some(current);
+                None
+            }
         }
+    }
 
-        Ok(())
+    fn is_focused(&self) -> bool {
+        let current = self.probe.active_window().map(|w| w.id);
+        current.is_some() && current == self.target.as_ref().map(|w| w.id)
+    }
+
+    /// Accept whatever window has focus now as the new target
+    fn retarget(&mut self) {
+        self.target = self.probe.active_window();
+        self.last_check = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl EventSink for Recorder {
+        fn emit_event(&self, event: &str, _payload: serde_json::Value) {
+            self.0.lock().push(event.to_string());
+        }
+    }
+
+    #[test]
+    fn corrected_mistakes_leave_the_count_at_the_content_length() {
+        let text = "The quick brown fox jumps over the lazy dog.\nPack my box with five dozen liquor jugs.\n";
+        let document = Document::plain("test.txt".to_string(), text.to_string());
+        let config = Config {
+            mistake_rate: 0.3,
+            correction_rate: 1.0,
+            ..Config::default()
+        };
+        let mut net = 0;
+        for chunk in plan::plan_content(&document, &config) {
+            for step in &chunk.steps {
+                net += step.action.net_chars();
+                assert!(net >= 0);
+            }
+        }
+        assert_eq!(net, text.chars().count() as isize);
+    }
+
+    #[test]
+    fn abort_stops_and_asks_for_undo() {
+        let engine = TypingEngine::new();
+        engine.abort(true);
+        assert!(engine.is_stopped());
+        assert!(engine.undo_signal.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn nothing_is_undone_when_nothing_is_left_typed() {
+        let engine = Arc::new(TypingEngine::new());
+        let events = Recorder::default();
+        engine.typed_chars.store(-2, Ordering::SeqCst);
+        engine.clone().undo(&events).await.unwrap();
+        assert_eq!(engine.typed_chars(), 0);
+        assert!(events.0.lock().is_empty());
     }
 }
@@ -0,0 +1,237 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::distribution::IntervalDistribution;
+use crate::config::Config;
+
+/// Bundled tables, by language code
+const BUNDLED: &[(&str, &str)] = &[
+    ("en", include_str!("../../data/ngrams/en.tsv")),
+    ("de", include_str!("../../data/ngrams/de.tsv")),
+];
+
+/// Language used when the configured one has no bundled table
+const FALLBACK_LANGUAGE: &str = "en";
+
+/// Timing of one n-gram's final keystroke
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct NgramStat {
+    /// Mean interval relative to the base delay (below 1 is faster)
+    pub ratio: f64,
+    /// Standard deviation of that ratio
+    #[serde(default = "default_spread")]
+    pub spread: f64,
+}
+
+fn default_spread() -> f64 {
+    0.05
+}
+
+impl NgramStat {
+    /// Draw a delay multiplier from `distribution`
+    pub fn sample(&self, distribution: &IntervalDistribution, rng: &mut impl Rng) -> f64 {
+        let cv = if self.ratio > 0.0 { self.spread.max(0.0) / self.ratio } else { 0.0 };
+        let factor = distribution.sample(self.ratio, cv, rng);
+        // The skewed families keep their tail
+        let (low, high) = match distribution {
+            IntervalDistribution::Normal => (0.5, 1.5),
+            _ => distribution.bounds(),
+        };
+        factor.clamp(self.ratio * low, self.ratio * high)
+    }
+}
+
+/// Digraph and trigraph timings. Keys are lowercased characters packed into
+/// a `u64` (21 bits each) so lookups never allocate.
+#[derive(Debug, Default)]
+pub struct NgramTable {
+    digraphs: HashMap<u64, NgramStat>,
+    trigraphs: HashMap<u64, NgramStat>,
+}
+
+/// User calibration file: the output of calibration, or any JSON object with
+/// `digraphs` and/or `trigraphs` maps from n-gram to `{ratio, spread}`
+#[derive(Debug, Default, Deserialize)]
+struct CalibrationFile {
+    #[serde(default)]
+    digraphs: HashMap<String, NgramStat>,
+    #[serde(default)]
+    trigraphs: HashMap<String, NgramStat>,
+}
+
+fn fold(c: char) -> u64 {
+    c.to_lowercase().next().unwrap_or(c) as u64
+}
+
+fn pack(chars: &[char]) -> u64 {
+    chars.iter().fold(0, |key, &c| (key << 21) | fold(c))
+}
+
+impl NgramTable {
+    /// Parse a bundled table: `ngram<TAB>ratio<TAB>spread` per line, `#` comments
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut table = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let parse_f64 = |field: Option<&&str>| field.and_then(|f| f.trim().parse::<f64>().ok());
+            let (Some(ngram), Some(ratio)) = (fields.first(), parse_f64(fields.get(1))) else {
+                return Err(format!("Invalid n-gram entry at line {}", i + 1));
+            };
+            let stat = NgramStat {
+                ratio,
+                spread: parse_f64(fields.get(2)).unwrap_or_else(default_spread),
+            };
+            table.insert(ngram, stat)?;
+        }
+        Ok(table)
+    }
+
+    fn insert(&mut self, ngram: &str, stat: NgramStat) -> Result<(), String> {
+        let chars: Vec<char> = ngram.chars().collect();
+        match chars.len() {
+            2 => self.digraphs.insert(pack(&chars), stat),
+            3 => self.trigraphs.insert(pack(&chars), stat),
+            _ => return Err(format!("'{}' is not a digraph or trigraph", ngram)),
+        };
+        Ok(())
+    }
+
+    /// Override entries with a user's calibration file
+    fn merge_file(&mut self, path: &str) -> Result<(), String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let file: CalibrationFile =
+            serde_json::from_str(&text).map_err(|e| format!("Invalid n-gram file {}: {}", path, e))?;
+        for (ngram, stat) in file.digraphs.iter().chain(&file.trigraphs) {
+            self.insert(ngram, *stat)?;
+        }
+        Ok(())
+    }
+
+    /// Bundled table for `language` (falling back to English)
+    pub fn bundled(language: &str) -> Self {
+        let text = BUNDLED
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(language))
+            .or_else(|| BUNDLED.iter().find(|(code, _)| *code == FALLBACK_LANGUAGE))
+            .map(|(_, text)| *text)
+            .unwrap_or_default();
+        Self::parse(text).unwrap_or_default()
+    }
+
+    /// Timing for typing `curr` after `prev` (and `before_prev`), preferring
+    /// the trigraph. The flag is true for a trigraph match.
+    pub fn lookup(&self, before_prev: Option<char>, prev: char, curr: char) -> Option<(NgramStat, bool)> {
+        if let Some(stat) = before_prev.and_then(|b| self.trigraphs.get(&pack(&[b, prev, curr]))) {
+            return Some((*stat, true));
+        }
+        self.digraphs.get(&pack(&[prev, curr])).map(|stat| (*stat, false))
+    }
+}
+
+/// Language and user file a table was built from
+pub type TableSource = (String, Option<String>);
+
+/// Loaded tables by source
+static TABLES: Lazy<Mutex<HashMap<TableSource, Arc<NgramTable>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The table for `config`'s language and calibration file. Tables are loaded
+/// once and shared; a broken user file falls back to the bundled table.
+pub fn table_for(config: &Config) -> Arc<NgramTable> {
+    let key = (config.language.to_ascii_lowercase(), config.ngram_file.clone());
+    TABLES
+        .lock()
+        .entry(key)
+        .or_insert_with(|| {
+            let mut table = NgramTable::bundled(&config.language);
+            if let Some(path) = &config.ngram_file {
+                if let Err(e) = table.merge_file(path) {
+                    eprintln!("Ignoring n-gram file: {}", e);
+                }
+            }
+            Arc::new(table)
+        })
+        .clone()
+}
+
+/// Forget loaded tables so edited calibration files are read again
+pub fn reload() {
+    TABLES.lock().clear();
+}
+
+/// Check that a calibration file can be used
+pub fn validate_file(path: &str) -> Result<(), String> {
+    NgramTable::default().merge_file(path)
+}
+
+/// Language codes with a bundled table
+pub fn languages() -> Vec<&'static str> {
+    BUNDLED.iter().map(|(code, _)| *code).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_parse_with_comments_and_default_spread() {
+        let table = NgramTable::parse("# ngram\tratio\tspread\n\nth\t0.8\t0.1\nThe\t0.7\n").unwrap();
+        assert_eq!(table.lookup(None, 't', 'h'), Some((NgramStat { ratio: 0.8, spread: 0.1 }, false)));
+        // Trigraphs win and case does not matter
+        assert_eq!(
+            table.lookup(Some('T'), 'H', 'E'),
+            Some((NgramStat { ratio: 0.7, spread: 0.05 }, true))
+        );
+        assert_eq!(table.lookup(Some('x'), 't', 'h').map(|(_, trigraph)| trigraph), Some(false));
+        assert_eq!(table.lookup(None, 'x', 'y'), None);
+    }
+
+    #[test]
+    fn bad_entries_are_reported_with_their_line() {
+        assert_eq!(NgramTable::parse("th\t0.8\nhe\tfast\n").unwrap_err(), "Invalid n-gram entry at line 2");
+        assert_eq!(NgramTable::parse("thei\t0.8\n").unwrap_err(), "'thei' is not a digraph or trigraph");
+    }
+
+    #[test]
+    fn bundled_tables_parse() {
+        for (code, text) in BUNDLED {
+            let table = NgramTable::parse(text).unwrap_or_else(|e| panic!("{}: {}", code, e));
+            assert!(!table.digraphs.is_empty(), "{}", code);
+        }
+        assert_eq!(NgramTable::bundled("xx").digraphs.len(), NgramTable::bundled("en").digraphs.len());
+    }
+
+    #[test]
+    fn calibration_files_override_the_bundled_table() {
+        let path = std::env::temp_dir().join(format!("ghostkeys-ngrams-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"digraphs": {"th": {"ratio": 2.0}}, "trigraphs": {}, "report": {}}"#).unwrap();
+        let path = path.to_str().unwrap();
+        let mut table = NgramTable::bundled("en");
+        table.merge_file(path).unwrap();
+        assert_eq!(table.lookup(None, 't', 'h').unwrap().0.ratio, 2.0);
+
+        std::fs::write(path, r#"{"digraphs": {"thei": {"ratio": 2.0}}}"#).unwrap();
+        assert!(validate_file(path).is_err());
+        std::fs::write(path, "\"nope\"").unwrap();
+        assert!(validate_file(path).unwrap_err().starts_with("Invalid n-gram file"));
+        std::fs::remove_file(path).unwrap();
+        assert!(validate_file(path).unwrap_err().starts_with("Failed to read"));
+    }
+
+    #[test]
+    fn samples_stay_near_the_ratio() {
+        let stat = NgramStat { ratio: 0.8, spread: 0.4 };
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let factor = stat.sample(&IntervalDistribution::Normal, &mut rng);
+            assert!((stat.ratio * 0.5..=stat.ratio * 1.5).contains(&factor), "{}", factor);
+        }
+    }
+}
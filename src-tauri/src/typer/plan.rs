@@ -0,0 +1,381 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::code::{CodeLanguage, CodeModel};
+use super::fatigue::FatigueState;
+use super::keyboard::KeyCombo;
+use super::mistakes::{generate_mistake, MistakeType};
+use super::ngrams::{self, NgramTable, TableSource};
+use super::stream::CharStream;
+use super::structure::Structure;
+use super::timing::{self, PauseBreakdown, Reason, TimingContext, WordContext, WordSpan};
+use super::words::{self, WordList};
+use crate::config::Config;
+use crate::content::{Document, Shortcut};
+
+/// A single keyboard action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "char", rename_all = "lowercase")]
+pub enum Action {
+    /// Type a character
+    Type(char),
+    /// Press backspace once
+    Backspace,
+    /// Do nothing (e.g. noticing a mistake)
+    Wait,
+    /// Press an editor shortcut
+    Shortcut(KeyCombo),
+}
+
+impl Action {
+    /// Change in the number of characters in the target field
+    pub fn net_chars(&self) -> isize {
+        match self {
+            Action::Type(_) => 1,
+            Action::Backspace => -1,
+            Action::Wait | Action::Shortcut(_) => 0,
+        }
+    }
+}
+
+/// An action followed by a delay
+#[derive(Debug, Clone, Serialize)]
+pub struct Step {
+    pub action: Action,
+    /// Time from this key going down to the next action
+    pub delay_ms: u64,
+    /// How long the key stays down (0 types it in one go). Longer than
+    /// `delay_ms` when the next key is rolled over this one.
+    pub hold_ms: u64,
+}
+
+/// Everything typed for one position of the content
+#[derive(Debug, Clone, Serialize)]
+pub struct Chunk {
+    /// Content index once this chunk has been typed
+    pub end_index: usize,
+    /// Keyboard actions, in order
+    pub steps: Vec<Step>,
+    /// Pause after the chunk before moving on
+    pub pause_ms: u64,
+    /// Mistake made in this chunk (if any)
+    pub mistake: Option<MistakeType>,
+    /// Whether the mistake was corrected
+    pub corrected: bool,
+    /// What shaped the delay for this position
+    pub reasons: Vec<Reason>,
+    /// Pauses included in the delay
+    pub pauses: PauseBreakdown,
+}
+
+impl Chunk {
+    /// Total time spent on this chunk
+    pub fn duration_ms(&self) -> u64 {
+        self.steps.iter().map(|s| s.delay_ms).sum::<u64>() + self.pause_ms
+    }
+}
+
+/// Turns content into keystrokes, one chunk at a time
+pub struct Planner {
+    /// Content, decoded a window at a time
+    stream: CharStream,
+    index: usize,
+    /// Language of source code content
+    language: Option<CodeLanguage>,
+    /// Where units of the current window start, for composition pauses
+    structure: Structure,
+    /// Tokenized current window, when the content is source code
+    code: Option<CodeModel>,
+    /// Word being typed, by window index
+    word: WordSpan,
+    /// Warmup and fatigue, advanced by the time each planned chunk takes
+    fatigue: FatigueState,
+    /// Editor shortcuts by content index, and how many have been planned
+    shortcuts: Vec<Shortcut>,
+    next_shortcut: usize,
+    /// N-gram timings and word list for the config last planned with
+    tables: Option<(Arc<NgramTable>, Arc<WordList>)>,
+    table_source: TableSource,
+}
+
+impl Planner {
+    /// Plan `content`, using the code timing model if it is source in `code`
+    pub fn new(content: Arc<str>, code: Option<CodeLanguage>) -> Self {
+        let mut planner = Self {
+            stream: CharStream::new(content),
+            index: 0,
+            language: code,
+            structure: Structure::default(),
+            code: None,
+            word: WordSpan::default(),
+            fatigue: FatigueState::default(),
+            shortcuts: Vec::new(),
+            next_shortcut: 0,
+            tables: None,
+            table_source: Default::default(),
+        };
+        planner.analyze_window();
+        planner
+    }
+
+    /// Plan a prepared document, including its editor shortcuts
+    pub fn for_document(document: &Document) -> Self {
+        Self::new(document.text.clone(), document.code_language).with_shortcuts(&document.shortcuts)
+    }
+
+    fn analyze_window(&mut self) {
+        let chars = self.stream.chars();
+        self.structure = Structure::analyze(chars);
+        self.code = self.language.map(|language| CodeModel::analyze(chars, language));
+        self.word.reset();
+    }
+
+    /// Press `shortcuts` before the characters they are placed at
+    pub fn with_shortcuts(mut self, shortcuts: &[Shortcut]) -> Self {
+        self.shortcuts = shortcuts.to_vec();
+        self.shortcuts.sort_by_key(|s| s.index);
+        self
+    }
+
+    pub fn total(&self) -> usize {
+        self.stream.total()
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Whether the next character starts a new line
+    pub fn at_paragraph_start(&self) -> bool {
+        self.index > 0 && self.stream.get(self.index - 1) == Some('\n')
+    }
+
+    /// Warmup and fatigue reached so far
+    pub fn fatigue(&self) -> FatigueState {
+        self.fatigue
+    }
+
+    /// Continue from the warmup and fatigue of earlier typing in the session
+    pub fn set_fatigue(&mut self, fatigue: FatigueState) {
+        self.fatigue = fatigue;
+    }
+
+    /// Account for a break in typing (pauses, waiting for a trigger)
+    pub fn rest(&mut self, rested: Duration, config: &Config) {
+        self.fatigue.rest(rested, &config.fatigue);
+    }
+
+    /// Jump to the break before the next paragraph, leaving the rest of the
+    /// current one untyped. The newlines themselves are still typed.
+    pub fn skip_paragraph(&mut self) {
+        self.index = self.stream.paragraph_end(self.index);
+    }
+
+    /// The n-gram table and word list for `config`, reloaded when its
+    /// language or n-gram file changes
+    fn tables_for(&mut self, config: &Config) -> (Arc<NgramTable>, Arc<WordList>) {
+        let source = (config.language.clone(), config.ngram_file.clone());
+        match &self.tables {
+            Some(tables) if self.table_source == source => tables.clone(),
+            _ => {
+                let tables = (ngrams::table_for(config), words::list_for(&config.language));
+                self.tables = Some(tables.clone());
+                self.table_source = source;
+                self.word.reset();
+                tables
+            }
+        }
+    }
+
+    /// Shortcut steps due before typing the character at the current index.
+    /// Shortcuts skipped over are still pressed so toggles stay balanced.
+    fn shortcut_steps(&mut self, config: &Config) -> Vec<Step> {
+        let mut steps = Vec::new();
+        while let Some(shortcut) = self.shortcuts.get(self.next_shortcut) {
+            if shortcut.index > self.index {
+                break;
+            }
+            steps.push(Step {
+                action: Action::Shortcut(shortcut.keys),
+                delay_ms: timing::shortcut_delay(config),
+                hold_ms: 0,
+            });
+            self.next_shortcut += 1;
+        }
+        steps
+    }
+
+    /// Plan the keystrokes for the next position using `config`
+    pub fn next_chunk(&mut self, config: &Config) -> Option<Chunk> {
+        let total_chars = self.stream.total();
+        let i = self.index;
+        if self.stream.advance_to(i) {
+            self.analyze_window();
+        }
+        let shortcut_steps = self.shortcut_steps(config);
+        if i >= total_chars {
+            // Formatting turned off after the last character
+            if shortcut_steps.is_empty() {
+                return None;
+            }
+            let chunk = Chunk {
+                end_index: i,
+                steps: shortcut_steps,
+                pause_ms: 0,
+                mistake: None,
+                corrected: false,
+                reasons: Vec::new(),
+                pauses: PauseBreakdown::default(),
+            };
+            self.fatigue.advance(Duration::from_millis(chunk.duration_ms()));
+            return Some(chunk);
+        }
+
+        let (ngrams, words) = self.tables_for(config);
+        let mut rng = rand::thread_rng();
+        let stream = &self.stream;
+        let chars = stream.chars();
+        // Position in the window
+        let local = i - stream.start();
+        let current_char = chars[local];
+        // No transpositions across a shortcut
+        let next_char = stream
+            .get(i + 1)
+            .filter(|_| self.shortcuts.get(self.next_shortcut).is_none_or(|s| s.index > i + 1));
+
+        let word = WordContext::track(chars, local, &mut self.word);
+        if config.word_difficulty {
+            self.word.analyze(chars, &words);
+        }
+
+        // Calculate delay using enhanced word-aware timing
+        let timing::Delay {
+            ms: delay,
+            reasons,
+            pauses,
+        } = timing::calculate_delay_v2(
+            config,
+            &TimingContext {
+                ngrams: &ngrams,
+                words: &words,
+                structure: &self.structure,
+                code: self.code.as_ref().filter(|_| config.code_timing),
+                fatigue: &self.fatigue,
+                word,
+                word_parts: self.word.parts(),
+            },
+            chars,
+            local,
+        );
+
+        // Maybe generate a mistake
+        let mistake_result = generate_mistake(current_char, next_char, config.mistake_rate);
+
+        let mut steps = shortcut_steps;
+        steps.extend(mistake_result.chars_to_type.iter().map(|c| Step {
+            action: Action::Type(*c),
+            delay_ms: delay / 2,
+            hold_ms: timing::key_hold(config, delay / 2),
+        }));
+
+        // If a mistake was made, maybe correct it
+        let mut corrected = false;
+        if mistake_result.mistake_made && rng.gen::<f64>() < config.correction_rate {
+            corrected = true;
+
+            // Wait before noticing mistake
+            steps.push(Step {
+                action: Action::Wait,
+                delay_ms: timing::notice_mistake_delay(),
+                hold_ms: 0,
+            });
+
+            // Backspace to remove wrong chars
+            let backspace_delay = timing::backspace_delay(config);
+            for _ in 0..mistake_result.chars_to_type.len() {
+                steps.push(Step {
+                    action: Action::Backspace,
+                    delay_ms: backspace_delay,
+                    hold_ms: timing::key_hold(config, backspace_delay),
+                });
+            }
+
+            // Type correctly
+            for c in (i..i + mistake_result.chars_consumed).filter_map(|j| stream.get(j)) {
+                steps.push(Step {
+                    action: Action::Type(c),
+                    delay_ms: delay,
+                    hold_ms: timing::key_hold(config, delay),
+                });
+            }
+        }
+
+        self.index = (i + mistake_result.chars_consumed).min(total_chars);
+
+        let chunk = Chunk {
+            end_index: self.index,
+            steps,
+            pause_ms: delay,
+            mistake: mistake_result.mistake_type,
+            corrected,
+            reasons,
+            pauses,
+        };
+        self.fatigue.advance(Duration::from_millis(chunk.duration_ms()));
+        Some(chunk)
+    }
+}
+
+/// Aggregate numbers for a plan
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlanSummary {
+    pub chars: usize,
+    pub keystrokes: usize,
+    pub backspaces: usize,
+    pub mistakes: usize,
+    pub corrected: usize,
+    pub duration_ms: u64,
+    pub effective_wpm: f64,
+    pub pauses: PauseBreakdown,
+}
+
+impl PlanSummary {
+    /// Summarize chunks as they are planned, without keeping them
+    pub fn from_chunks(chunks: impl IntoIterator<Item = Chunk>) -> Self {
+        let mut summary = Self::default();
+        for chunk in chunks {
+            summary.chars = chunk.end_index;
+            summary.duration_ms += chunk.duration_ms();
+            summary.pauses.add(&chunk.pauses);
+            for step in &chunk.steps {
+                match step.action {
+                    Action::Type(_) => summary.keystrokes += 1,
+                    Action::Backspace => {
+                        summary.keystrokes += 1;
+                        summary.backspaces += 1;
+                    }
+                    Action::Wait | Action::Shortcut(_) => {}
+                }
+            }
+            if chunk.mistake.is_some() {
+                summary.mistakes += 1;
+                if chunk.corrected {
+                    summary.corrected += 1;
+                }
+            }
+        }
+        if summary.duration_ms > 0 {
+            let minutes = summary.duration_ms as f64 / 60_000.0;
+            summary.effective_wpm = (summary.chars as f64 / 5.0) / minutes;
+        }
+        summary
+    }
+}
+
+/// Plan the whole document (for previews and exports), one chunk at a time
+pub fn plan_content<'a>(document: &Document, config: &'a Config) -> impl Iterator<Item = Chunk> + 'a {
+    let mut planner = Planner::for_document(document);
+    std::iter::from_fn(move || planner.next_chunk(config))
+}
@@ -0,0 +1,185 @@
+use serde::Serialize;
+use std::sync::Arc;
+
+use super::code::CodeLanguage;
+use super::trigger::StartTrigger;
+use crate::config::Config;
+use crate::content::{Document, Shortcut};
+
+/// A document waiting to be typed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueItem {
+    pub id: u64,
+    pub name: String,
+    /// Shared so copies of the queue don't duplicate large documents
+    #[serde(skip)]
+    pub content: Arc<str>,
+    pub char_count: usize,
+    /// Language of source code content, detected from the name's extension
+    pub code_language: Option<CodeLanguage>,
+    /// Editor shortcuts pressed while typing the content
+    #[serde(skip)]
+    pub shortcuts: Vec<Shortcut>,
+    /// Template variables the content needs a value for
+    pub variables: Vec<String>,
+    /// Built-in profile name the item was queued with
+    pub profile: Option<String>,
+    /// Config used for this item instead of the active one
+    pub config: Option<Config>,
+    /// Start condition for this item; `None` uses the config's `start_trigger`
+    pub trigger: Option<StartTrigger>,
+}
+
+/// Ordered list of documents typed one after another
+#[derive(Debug, Default)]
+pub struct ContentQueue {
+    items: Vec<QueueItem>,
+    next_id: u64,
+}
+
+impl ContentQueue {
+    pub fn items(&self) -> &[QueueItem] {
+        &self.items
+    }
+
+    pub fn get(&self, index: usize) -> Option<&QueueItem> {
+        self.items.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Append a document. A `profile` name is resolved to its config unless
+    /// an explicit `config` is given.
+    pub fn push(
+        &mut self,
+        document: Document,
+        profile: Option<String>,
+        config: Option<Config>,
+        trigger: Option<StartTrigger>,
+    ) -> Result<&QueueItem, String> {
+        let config = match (config, &profile) {
+            (Some(config), _) => Some(config),
+            (None, Some(name)) => Some(
+                Config::profile(name).ok_or_else(|| format!("Unknown profile '{}'", name))?,
+            ),
+            (None, None) => None,
+        };
+
+        self.next_id += 1;
+        self.items.push(QueueItem {
+            id: self.next_id,
+            name: document.name,
+            char_count: document.text.chars().count(),
+            content: document.text,
+            code_language: document.code_language,
+            shortcuts: document.shortcuts,
+            variables: document.variables,
+            profile,
+            config,
+            trigger,
+        });
+        Ok(&self.items[self.items.len() - 1])
+    }
+
+    /// Replace the whole queue with a single document
+    pub fn replace(&mut self, document: Document) {
+        self.items.clear();
+        let _ = self.push(document, None, None, None);
+    }
+
+    pub fn remove(&mut self, id: u64) -> Result<QueueItem, String> {
+        let index = self.position(id)?;
+        Ok(self.items.remove(index))
+    }
+
+    /// Move an item to `index` (clamped to the end of the queue)
+    pub fn move_item(&mut self, id: u64, index: usize) -> Result<(), String> {
+        let from = self.position(id)?;
+        let item = self.items.remove(from);
+        let to = index.min(self.items.len());
+        self.items.insert(to, item);
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    fn position(&self, id: u64) -> Result<usize, String> {
+        self.items
+            .iter()
+            .position(|item| item.id == id)
+            .ok_or_else(|| format!("No queue item with id {}", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(name: &str) -> Document {
+        Document::plain(name.to_string(), format!("text of {}", name))
+    }
+
+    fn queue(names: &[&str]) -> ContentQueue {
+        let mut queue = ContentQueue::default();
+        for name in names {
+            queue.push(document(name), None, None, None).unwrap();
+        }
+        queue
+    }
+
+    fn names(queue: &ContentQueue) -> Vec<&str> {
+        queue.items().iter().map(|item| item.name.as_str()).collect()
+    }
+
+    #[test]
+    fn ids_are_never_reused() {
+        let mut queue = queue(&["a", "b"]);
+        queue.remove(2).unwrap();
+        queue.replace(document("c"));
+        assert_eq!(queue.items()[0].id, 3);
+        assert_eq!(queue.items()[0].char_count, 9);
+    }
+
+    #[test]
+    fn profiles_resolve_unless_a_config_is_given() {
+        let mut queue = ContentQueue::default();
+        let item = queue.push(document("a"), Some("fast".to_string()), None, None).unwrap();
+        assert_eq!(item.config.as_ref().unwrap().base_wpm, Config::profile("fast").unwrap().base_wpm);
+
+        let config = Config { base_wpm: 42, ..Config::default() };
+        let item = queue.push(document("b"), Some("fast".to_string()), Some(config), None).unwrap();
+        assert_eq!(item.config.as_ref().unwrap().base_wpm, 42);
+
+        assert_eq!(
+            queue.push(document("c"), Some("nope".to_string()), None, None).unwrap_err(),
+            "Unknown profile 'nope'"
+        );
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn items_move_and_clamp_to_the_end() {
+        let mut queue = queue(&["a", "b", "c"]);
+        queue.move_item(3, 0).unwrap();
+        assert_eq!(names(&queue), ["c", "a", "b"]);
+        queue.move_item(3, 99).unwrap();
+        assert_eq!(names(&queue), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn unknown_ids_are_errors() {
+        let mut queue = queue(&["a"]);
+        assert_eq!(queue.remove(7).unwrap_err(), "No queue item with id 7");
+        assert!(queue.move_item(7, 0).is_err());
+        assert_eq!(names(&queue), ["a"]);
+    }
+}
@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::mistakes::MistakeType;
+use super::plan::{Action, Chunk, Step};
+use super::timing::{PauseBreakdown, Reason};
+
+/// One keystroke as it was sent
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeystrokeRecord {
+    /// Milliseconds since the session started
+    pub t_ms: u64,
+    /// Queue item being typed
+    pub item: usize,
+    /// Content index of the position this keystroke belongs to
+    pub index: usize,
+    #[serde(flatten)]
+    pub action: Action,
+    /// Planned delay after the keystroke
+    pub delay_ms: u64,
+    /// Planned time the key was held down
+    pub hold_ms: u64,
+    /// Mistake made at this position (if any)
+    pub mistake: Option<MistakeType>,
+    pub reasons: Vec<Reason>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct MistakeCount {
+    pub made: usize,
+    pub corrected: usize,
+}
+
+/// Summary of a finished (or stopped) run
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionReport {
+    /// Wall-clock start (Unix epoch milliseconds)
+    pub started_at: u64,
+    /// Time spent typing, excluding start triggers, pauses and breaks
+    pub duration_ms: u64,
+    /// Time spent paused (by the user or the focus guard)
+    pub paused_ms: u64,
+    /// Scheduled breaks taken, and their total time
+    pub breaks: usize,
+    pub break_ms: u64,
+    /// Content characters typed
+    pub chars: usize,
+    pub keystrokes: usize,
+    pub backspaces: usize,
+    /// Characters per minute over `duration_ms`, in words of five characters
+    pub realized_wpm: f64,
+    pub mistakes: usize,
+    pub corrected: usize,
+    pub mistakes_by_type: HashMap<MistakeType, MistakeCount>,
+    pub pauses: PauseBreakdown,
+    /// Whether the run was stopped before the end
+    pub stopped: bool,
+}
+
+/// Keystroke log file formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Jsonl,
+    Csv,
+}
+
+/// Collects statistics while a run is typing
+#[derive(Debug, Default)]
+pub struct SessionRecorder {
+    started: Option<Instant>,
+    report: SessionReport,
+    /// Keystroke log, when enabled
+    log: Option<Vec<KeystrokeRecord>>,
+}
+
+impl SessionRecorder {
+    /// Start a new session, optionally keeping a per-keystroke log
+    pub fn start(keep_log: bool) -> Self {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        Self {
+            started: Some(Instant::now()),
+            report: SessionReport {
+                started_at,
+                ..Default::default()
+            },
+            log: keep_log.then(Vec::new),
+        }
+    }
+
+    /// Record a keystroke about to be sent for `chunk`
+    pub fn keystroke(&mut self, item: usize, index: usize, chunk: &Chunk, step: &Step) {
+        match step.action {
+            Action::Type(_) => self.report.keystrokes += 1,
+            Action::Backspace => {
+                self.report.keystrokes += 1;
+                self.report.backspaces += 1;
+            }
+            Action::Wait | Action::Shortcut(_) => {}
+        }
+
+        if let Some(log) = self.log.as_mut() {
+            let t_ms = self
+                .started
+                .map(|s| s.elapsed().as_millis() as u64)
+                .unwrap_or(0);
+            log.push(KeystrokeRecord {
+                t_ms,
+                item,
+                index,
+                action: step.action,
+                delay_ms: step.delay_ms,
+                hold_ms: step.hold_ms,
+                mistake: chunk.mistake,
+                reasons: chunk.reasons.clone(),
+            });
+        }
+    }
+
+    /// Record a fully typed chunk
+    pub fn chunk(&mut self, chunk: &Chunk, chars: usize) {
+        self.report.chars += chars;
+        self.report.pauses.add(&chunk.pauses);
+        if let Some(mistake) = chunk.mistake {
+            let count = self.report.mistakes_by_type.entry(mistake).or_default();
+            count.made += 1;
+            self.report.mistakes += 1;
+            if chunk.corrected {
+                count.corrected += 1;
+                self.report.corrected += 1;
+            }
+        }
+    }
+
+    pub fn add_typing_time(&mut self, elapsed: Duration) {
+        self.report.duration_ms += elapsed.as_millis() as u64;
+    }
+
+    pub fn add_paused_time(&mut self, elapsed: Duration) {
+        self.report.paused_ms += elapsed.as_millis() as u64;
+    }
+
+    pub fn add_break(&mut self, elapsed: Duration) {
+        self.report.breaks += 1;
+        self.report.break_ms += elapsed.as_millis() as u64;
+    }
+
+    /// Close the session, returning the report and keystroke log
+    pub fn finish(mut self, stopped: bool) -> (SessionReport, Vec<KeystrokeRecord>) {
+        let report = &mut self.report;
+        report.stopped = stopped;
+        report.duration_ms = report
+            .duration_ms
+            .saturating_sub(report.paused_ms + report.break_ms);
+        if report.duration_ms > 0 {
+            let minutes = report.duration_ms as f64 / 60_000.0;
+            report.realized_wpm = (report.chars as f64 / 5.0) / minutes;
+        }
+        (self.report, self.log.unwrap_or_default())
+    }
+}
+
+/// Serialize a keystroke log
+pub fn format_log(log: &[KeystrokeRecord], format: LogFormat) -> Result<String, String> {
+    let mut out = String::new();
+    match format {
+        LogFormat::Jsonl => {
+            for record in log {
+                out.push_str(&serde_json::to_string(record).map_err(|e| e.to_string())?);
+                out.push('\n');
+            }
+        }
+        LogFormat::Csv => {
+            out.push_str("t_ms,item,index,action,char,delay_ms,hold_ms,mistake,reasons\n");
+            for r in log {
+                let (action, c) = match r.action {
+                    Action::Type(c) => ("type", c.to_string()),
+                    Action::Backspace => ("backspace", String::new()),
+                    Action::Wait => ("wait", String::new()),
+                    Action::Shortcut(keys) => ("shortcut", keys.to_string()),
+                };
+                let mistake = r.mistake.map(|m| m.as_str()).unwrap_or_default();
+                let reasons: Vec<&str> = r.reasons.iter().map(Reason::as_str).collect();
+                let _ = writeln!(
+                    out,
+                    "{},{},{},{},{},{},{},{},{}",
+                    r.t_ms,
+                    r.item,
+                    r.index,
+                    action,
+                    csv_field(&c),
+                    r.delay_ms,
+                    r.hold_ms,
+                    mistake,
+                    reasons.join("|")
+                );
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Quote a CSV field if it contains separators, quotes or line breaks
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+/// Characters decoded past the typing position before the window looks for
+/// a paragraph break to end at
+const WINDOW_CHARS: usize = 64 * 1024;
+/// Past this, the window ends at any line break
+const MAX_WINDOW_CHARS: usize = 4 * WINDOW_CHARS;
+/// Characters kept from before the typing position when the window moves,
+/// for n-grams and the word being typed
+const KEEP_CHARS: usize = 64;
+
+/// Content decoded a window at a time, so a large document is never held as
+/// one `Vec<char>`. Windows end at paragraph breaks where possible, so the
+/// structure and code analysis of a window matches that of the whole text.
+pub struct CharStream {
+    text: Arc<str>,
+    /// Decoded characters
+    chars: Vec<char>,
+    /// Content index of `chars[0]`
+    start: usize,
+    /// Byte offsets in `text` of `chars[0]` and of the character after the
+    /// window
+    byte_start: usize,
+    byte_end: usize,
+    total: usize,
+}
+
+impl CharStream {
+    pub fn new(text: Arc<str>) -> Self {
+        let mut stream = Self {
+            total: text.chars().count(),
+            text,
+            chars: Vec::new(),
+            start: 0,
+            byte_start: 0,
+            byte_end: 0,
+        };
+        stream.fill(0, 0);
+        stream
+    }
+
+    /// Characters in the whole content
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// The decoded window
+    pub fn chars(&self) -> &[char] {
+        &self.chars
+    }
+
+    /// Content index of the first character of [`Self::chars`]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Content index just past the window
+    pub fn end(&self) -> usize {
+        self.start + self.chars.len()
+    }
+
+    /// Character at content index `index`, if it is in the window or the one
+    /// right after it
+    pub fn get(&self, index: usize) -> Option<char> {
+        let local = index.checked_sub(self.start)?;
+        match self.chars.get(local) {
+            Some(&c) => Some(c),
+            None if local == self.chars.len() => self.text[self.byte_end..].chars().next(),
+            None => None,
+        }
+    }
+
+    /// Move the window forward so it holds `index`, returning whether it
+    /// moved (the analysis of the old window no longer applies)
+    pub fn advance_to(&mut self, index: usize) -> bool {
+        if index < self.end() || self.byte_end >= self.text.len() {
+            return false;
+        }
+        let skipped: usize = self.text[self.byte_end..]
+            .chars()
+            .take(index - self.end())
+            .map(char::len_utf8)
+            .sum();
+        self.fill(index, self.byte_end + skipped);
+        true
+    }
+
+    /// Content index of the end of the paragraph around `index`: the next
+    /// blank line, or failing that the next line break. Line breaks right
+    /// at `index` are passed over first.
+    pub fn paragraph_end(&self, index: usize) -> usize {
+        let Some(local) = index.checked_sub(self.start).filter(|&i| i <= self.chars.len()) else {
+            return index;
+        };
+        let from = self.byte_start + self.chars[..local].iter().map(|c| c.len_utf8()).sum::<usize>();
+        let rest = &self.text[from..];
+        let body = rest.trim_start_matches('\n');
+        let lead = rest.len() - body.len();
+        let end = body.find("\n\n").or_else(|| body.find('\n')).unwrap_or(body.len());
+        index + lead + body[..end].chars().count()
+    }
+
+    /// Decode a new window for content index `index` at byte `byte`
+    fn fill(&mut self, index: usize, byte: usize) {
+        let mut kept: Vec<char> = self.text[..byte].chars().rev().take(KEEP_CHARS).collect();
+        kept.reverse();
+        self.start = index - kept.len();
+        self.byte_start = byte - kept.iter().map(|c| c.len_utf8()).sum::<usize>();
+        self.chars = kept;
+
+        let mut decoded = 0;
+        self.byte_end = byte;
+        for c in self.text[byte..].chars() {
+            self.chars.push(c);
+            self.byte_end += c.len_utf8();
+            decoded += 1;
+            if c == '\n' {
+                let paragraph = self.chars.len() >= 2 && self.chars[self.chars.len() - 2] == '\n';
+                if (decoded >= WINDOW_CHARS && paragraph) || decoded >= MAX_WINDOW_CHARS {
+                    break;
+                }
+            } else if decoded >= 2 * MAX_WINDOW_CHARS {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Read the whole content through the stream as the planner does,
+    /// checking each window as it goes
+    fn read_all(text: &str) -> (String, Vec<usize>) {
+        let mut stream = CharStream::new(text.into());
+        let mut read = String::new();
+        let mut ends = Vec::new();
+        for index in 0..stream.total() {
+            if index == stream.end() {
+                ends.push(index);
+                assert!(stream.advance_to(index));
+                assert_eq!(stream.start(), index - KEEP_CHARS.min(index));
+            }
+            read.push(stream.get(index).unwrap());
+        }
+        assert_eq!(stream.get(stream.total()), None);
+        (read, ends)
+    }
+
+    #[test]
+    fn small_content_is_one_window() {
+        let mut stream = CharStream::new("héllo\n\nwörld".into());
+        assert_eq!((stream.start(), stream.end(), stream.total()), (0, 12, 12));
+        assert!(!stream.advance_to(12));
+        assert_eq!(stream.paragraph_end(0), 5);
+        assert_eq!(stream.paragraph_end(5), 12);
+    }
+
+    #[test]
+    fn windows_end_at_paragraph_breaks() {
+        let text = format!("{}\n\n", "é".repeat(999)).repeat(200);
+        let (read, ends) = read_all(&text);
+        assert_eq!(read, text);
+        assert!(!ends.is_empty());
+        for end in ends {
+            assert_eq!(end % 1001, 0, "window ended inside a paragraph at {}", end);
+            assert!(end >= WINDOW_CHARS);
+        }
+    }
+
+    #[test]
+    fn long_paragraphs_end_at_a_line_break() {
+        let text = format!("{}\n", "ü".repeat(99)).repeat(6000);
+        let (read, ends) = read_all(&text);
+        assert_eq!(read, text);
+        // The first line break once the window is at its longest
+        assert_eq!(ends[0], MAX_WINDOW_CHARS.div_ceil(100) * 100);
+    }
+
+    #[test]
+    fn text_without_line_breaks_is_cut_anyway() {
+        let text = "ab€".repeat(MAX_WINDOW_CHARS);
+        let (read, ends) = read_all(&text);
+        assert_eq!(read, text);
+        assert_eq!(ends, [2 * MAX_WINDOW_CHARS]);
+    }
+
+    #[test]
+    fn advancing_past_the_window_skips_ahead() {
+        let text = format!("{}\n", "x".repeat(99)).repeat(6000);
+        let mut stream = CharStream::new(text.as_str().into());
+        let target = stream.end() + 12_345;
+        assert!(stream.advance_to(target));
+        assert_eq!(stream.get(target), text[target..].chars().next());
+        assert_eq!(stream.paragraph_end(target), target + 100 - target % 100 - 1);
+        assert_eq!(stream.get(stream.start()), text[stream.start()..].chars().next());
+    }
+}
@@ -0,0 +1,293 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Kinds of text units a writer pauses before
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnitKind {
+    Paragraph,
+    Heading,
+    ListItem,
+    Sentence,
+    Clause,
+}
+
+impl UnitKind {
+    /// Pause weight relative to starting a sentence
+    fn weight(&self) -> f64 {
+        match self {
+            Self::Paragraph => 1.6,
+            Self::Heading => 1.2,
+            Self::ListItem => 0.8,
+            Self::Sentence => 1.0,
+            Self::Clause => 0.35,
+        }
+    }
+
+    /// Word count of a unit of average complexity
+    fn typical_words(&self) -> f64 {
+        match self {
+            Self::Paragraph => 60.0,
+            Self::Heading | Self::ListItem => 6.0,
+            Self::Sentence => 15.0,
+            Self::Clause => 6.0,
+        }
+    }
+}
+
+/// A unit of the content, measured from where it starts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Unit {
+    pub kind: UnitKind,
+    pub words: usize,
+    /// Commas, semicolons and colons inside the unit
+    pub clauses: usize,
+}
+
+impl Unit {
+    /// Pause multiplier: the kind's weight, scaled by how long and
+    /// involved the unit is (between half and double)
+    pub fn scale(&self) -> f64 {
+        let length = (self.words as f64 / self.kind.typical_words()).sqrt();
+        let nesting = 1.0 + 0.15 * self.clauses.min(4) as f64;
+        self.kind.weight() * (length * nesting).clamp(0.5, 2.0)
+    }
+}
+
+/// Where paragraphs, headings, list items, sentences and clauses start
+#[derive(Debug, Default)]
+pub struct Structure {
+    units: HashMap<usize, Unit>,
+}
+
+/// How far [`measure`] reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Scope {
+    Clause,
+    Sentence,
+    Line,
+    Paragraph,
+}
+
+/// Words that end in a period without ending the sentence
+const ABBREVIATIONS: &[&str] = &["e.g", "i.e", "etc", "mr", "mrs", "ms", "dr", "vs", "cf", "z.b", "d.h", "bzw", "usw"];
+
+impl Structure {
+    pub fn analyze(chars: &[char]) -> Self {
+        let mut units = HashMap::new();
+        let mut blank_before = true;
+        let mut line_start = 0;
+
+        while line_start < chars.len() {
+            let line_end = (line_start..chars.len())
+                .find(|&i| chars[i] == '\n')
+                .unwrap_or(chars.len());
+            let Some(start) = (line_start..line_end).find(|&i| !chars[i].is_whitespace()) else {
+                blank_before = true;
+                line_start = line_end + 1;
+                continue;
+            };
+
+            let line = &chars[start..line_end];
+            let block = if is_list_item(line) {
+                Some((UnitKind::ListItem, Scope::Line))
+            } else if is_heading(line, blank_before, is_blank_line(chars, line_end + 1)) {
+                Some((UnitKind::Heading, Scope::Line))
+            } else if blank_before {
+                Some((UnitKind::Paragraph, Scope::Paragraph))
+            } else {
+                None
+            };
+            if let Some((kind, scope)) = block {
+                let (words, clauses) = measure(chars, start, scope);
+                units.insert(start, Unit { kind, words, clauses });
+            }
+            blank_before = false;
+
+            // Sentences and clauses within the line
+            for i in start..line_end {
+                let scope = if ends_sentence(chars, i) {
+                    Scope::Sentence
+                } else if matches!(chars[i], ',' | ';' | ':') && chars.get(i + 1) == Some(&' ') {
+                    Scope::Clause
+                } else {
+                    continue;
+                };
+                let Some(next) = (i + 1..line_end).find(|&j| !chars[j].is_whitespace()) else {
+                    continue;
+                };
+                if scope == Scope::Sentence && !(chars[next].is_uppercase() || chars[next].is_ascii_digit()) {
+                    continue;
+                }
+                let kind = if scope == Scope::Sentence { UnitKind::Sentence } else { UnitKind::Clause };
+                let (words, clauses) = measure(chars, next, scope);
+                units.entry(next).or_insert(Unit { kind, words, clauses });
+            }
+
+            line_start = line_end + 1;
+        }
+
+        Self { units }
+    }
+
+    /// The unit starting at `index`, if any
+    pub fn unit_at(&self, index: usize) -> Option<&Unit> {
+        self.units.get(&index)
+    }
+}
+
+/// Count words and clause breaks from `from` to the end of `scope`
+fn measure(chars: &[char], from: usize, scope: Scope) -> (usize, usize) {
+    let mut words = 0;
+    let mut clauses = 0;
+    let mut in_word = false;
+
+    for i in from..chars.len() {
+        let c = chars[i];
+        if c == '\n' && (scope <= Scope::Line || is_blank_line(chars, i + 1)) {
+            break;
+        }
+        if c.is_whitespace() {
+            in_word = false;
+            continue;
+        }
+        if !in_word {
+            words += 1;
+            in_word = true;
+        }
+        if matches!(c, ',' | ';' | ':') {
+            if scope == Scope::Clause {
+                break;
+            }
+            clauses += 1;
+        }
+        if scope <= Scope::Sentence && ends_sentence(chars, i) {
+            break;
+        }
+    }
+    (words, clauses)
+}
+
+/// Whether `chars[i]` ends a sentence: terminal punctuation followed by
+/// whitespace, not after a known abbreviation
+fn ends_sentence(chars: &[char], i: usize) -> bool {
+    if !matches!(chars[i], '.' | '!' | '?') || !chars.get(i + 1).is_none_or(|c| c.is_whitespace()) {
+        return false;
+    }
+    if chars[i] != '.' {
+        return true;
+    }
+    let word_start = (0..i)
+        .rev()
+        .find(|&j| chars[j].is_whitespace())
+        .map_or(0, |j| j + 1);
+    let word: String = chars[word_start..i].iter().collect::<String>().to_lowercase();
+    // Initials ("J. Smith") and abbreviations
+    word.chars().count() > 1 && !ABBREVIATIONS.contains(&word.as_str())
+}
+
+/// Whether the line starting at `start` is empty or whitespace only
+fn is_blank_line(chars: &[char], start: usize) -> bool {
+    chars[start.min(chars.len())..]
+        .iter()
+        .take_while(|&&c| c != '\n')
+        .all(|c| c.is_whitespace())
+}
+
+/// Bulleted ("- ", "* ", "+ ", "• ") or numbered ("1. ", "2) ") line
+fn is_list_item(line: &[char]) -> bool {
+    match line {
+        ['-' | '*' | '+' | '•', ' ', ..] => true,
+        _ => {
+            let digits = line.iter().take_while(|c| c.is_ascii_digit()).count();
+            digits > 0 && matches!(line.get(digits..digits + 2), Some(['.' | ')', ' ']))
+        }
+    }
+}
+
+/// Markdown heading, or a short standalone line without closing punctuation
+fn is_heading(line: &[char], blank_before: bool, blank_after: bool) -> bool {
+    if line.first() == Some(&'#') {
+        return true;
+    }
+    let text: String = line.iter().collect();
+    let text = text.trim_end();
+    let words = text.split_whitespace().count();
+    blank_before
+        && blank_after
+        && words <= 8
+        && !text.ends_with(['.', '!', '?', ',', ';', ':'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Units of `text` as (first word, kind, words, clauses), in order
+    fn units(text: &str) -> Vec<(String, UnitKind, usize, usize)> {
+        let chars: Vec<char> = text.chars().collect();
+        let structure = Structure::analyze(&chars);
+        let mut units: Vec<_> = structure.units.iter().collect();
+        units.sort_by_key(|(&index, _)| index);
+        units
+            .into_iter()
+            .map(|(&index, unit)| {
+                let word: String = chars[index..].iter().take_while(|c| !c.is_whitespace()).collect();
+                (word, unit.kind, unit.words, unit.clauses)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blocks_sentences_and_clauses() {
+        let text = "Getting Started\n\nInstall it first. Then, if it fails, retry.\n\n- one item\n2) two items here\n";
+        assert_eq!(
+            units(text),
+            [
+                ("Getting".to_string(), UnitKind::Heading, 2, 0),
+                ("Install".to_string(), UnitKind::Paragraph, 8, 2),
+                ("Then,".to_string(), UnitKind::Sentence, 5, 2),
+                ("if".to_string(), UnitKind::Clause, 3, 0),
+                ("retry.".to_string(), UnitKind::Clause, 1, 0),
+                ("-".to_string(), UnitKind::ListItem, 3, 0),
+                ("2)".to_string(), UnitKind::ListItem, 4, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn abbreviations_and_initials_do_not_end_sentences() {
+        let text = "See e.g. Dr. Smith and J. Doe. Next one.";
+        let sentences: Vec<String> = units(text)
+            .into_iter()
+            .filter(|unit| unit.1 == UnitKind::Sentence)
+            .map(|unit| unit.0)
+            .collect();
+        assert_eq!(sentences, ["Next"]);
+    }
+
+    #[test]
+    fn sentences_need_a_capital_or_digit_after_them() {
+        let kinds: Vec<UnitKind> = units("Version 2. released. 3 bugs fixed.").into_iter().map(|unit| unit.1).collect();
+        assert_eq!(kinds, [UnitKind::Paragraph, UnitKind::Sentence]);
+    }
+
+    #[test]
+    fn headings_need_blank_lines_or_a_hash() {
+        let kinds = |text: &str| units(text).into_iter().map(|unit| unit.1).collect::<Vec<_>>();
+        assert_eq!(kinds("# Title\nbody\n"), [UnitKind::Heading]);
+        assert_eq!(kinds("Short line\nbody\n"), [UnitKind::Paragraph]);
+        assert_eq!(kinds("\nEnds with a colon:\n\nbody.\n"), [UnitKind::Paragraph, UnitKind::Paragraph]);
+    }
+
+    #[test]
+    fn scale_grows_with_length_and_clauses_within_bounds() {
+        let unit = |words, clauses| Unit { kind: UnitKind::Sentence, words, clauses };
+        assert_eq!(unit(15, 0).scale(), 1.0);
+        assert!(unit(30, 0).scale() > unit(15, 0).scale());
+        assert!(unit(15, 2).scale() > unit(15, 0).scale());
+        assert_eq!(unit(1, 0).scale(), 0.5);
+        assert_eq!(unit(1000, 10).scale(), 2.0);
+    }
+}
@@ -1,6 +1,12 @@
 use rand::Rng;
-use rand_distr::{Distribution, Normal};
-
+use serde::Serialize;
+
+use super::distribution::IntervalDistribution;
+use super::code::{self, CodeModel, Token};
+use super::fatigue::FatigueState;
+use super::ngrams::NgramTable;
+use super::structure::Structure;
+use super::words::{WordList, WordParts};
 use crate::config::Config;
 
 /// Calculate the base delay between keystrokes in milliseconds
@@ -13,29 +19,16 @@ pub fn base_delay_ms(wpm: u32) -> u64 {
     (12000 / wpm as u64).max(20) // Minimum 20ms
 }
 
-/// Add gaussian variance to a delay
-pub fn add_variance(delay_ms: u64, variance: f64) -> u64 {
+/// Add random variance to a delay, drawn from the configured distribution
+pub fn add_variance(delay_ms: u64, variance: f64, distribution: &IntervalDistribution) -> u64 {
     if variance <= 0.0 {
         return delay_ms;
     }
 
-    let mean = delay_ms as f64;
-    let std_dev = mean * variance;
-
-    // Create normal distribution
-    let normal = match Normal::new(mean, std_dev) {
-        Ok(n) => n,
-        Err(_) => return delay_ms,
-    };
-
     let mut rng = rand::thread_rng();
-    let varied = normal.sample(&mut rng);
+    let varied = distribution.sample_clamped(delay_ms as f64, variance, &mut rng);
 
-    // Clamp to reasonable range (50% to 200% of original)
-    let min = (delay_ms as f64 * 0.5) as u64;
-    let max = (delay_ms as f64 * 2.0) as u64;
-
-    (varied as u64).clamp(min.max(10), max)
+    (varied as u64).max(10)
 }
 
 /// Check if character is a word boundary
@@ -47,31 +40,55 @@ fn is_word_boundary(c: char) -> bool {
 /// Returns 0 for left hand, 1 for right hand, 2 for either/special
 fn get_hand(c: char) -> u8 {
     match c.to_ascii_lowercase() {
-        'q' | 'w' | 'e' | 'r' | 't' | 'a' | 's' | 'd' | 'f' | 'g' | 'z' | 'x' | 'c' | 'v' | 'b' | '1' | '2' | '3' | '4' | '5' | '`' | '~' => 0,
+        'q' | 'w' | 'e' | 'r' | 't' | 'a' | 's' | 'd' | 'f' | 'g' | 'z' | 'x' | 'c' | 'v' | 'b' | '1' | '2' | '3' | '4' | '5' | '`' | '~' | '!' | '@' | '#' | '$' | '%' => 0,
         'y' | 'u' | 'i' | 'o' | 'p' | 'h' | 'j' | 'k' | 'l' | 'n' | 'm' | '6' | '7' | '8' | '9' | '0' | '-' | '=' | '[' | ']' | '\\' | ';' | '\'' | ',' | '.' | '/' => 1,
+        // Shifted symbols on the right half
+        '^' | '&' | '*' | '(' | ')' | '_' | '+' | '{' | '}' | '|' | ':' | '"' | '<' | '>' | '?' => 1,
         _ => 2,
     }
 }
 
-/// Check if two characters form a common digraph (typed faster due to muscle memory)
-fn is_common_digraph(prev: char, curr: char) -> bool {
-    let pair = format!("{}{}", prev.to_ascii_lowercase(), curr.to_ascii_lowercase());
-    matches!(pair.as_str(),
-        // Common English digraphs
-        "th" | "he" | "in" | "er" | "an" | "re" | "on" | "at" | "en" | "nd" |
-        "ti" | "es" | "or" | "te" | "of" | "ed" | "is" | "it" | "al" | "ar" |
-        "st" | "to" | "nt" | "ng" | "ha" | "as" | "ou" | "io" | "le" |
-        "ve" | "co" | "me" | "de" | "hi" | "ri" | "ro" | "ic" | "ne" | "ea" |
-        "ra" | "ce" | "li" | "ch" | "ll" | "be" | "ma" | "si" | "om" | "ur" |
-        // Common programming digraphs
-        "if" | "el" | "fo" | "wh" | "tu" | "rn" | "fu" | "nc" |
-        "ct" | "va" | "et" | "ue" | "tr" | "fa" |
-        "ls" | "nu" | "un" | "fi" | "cl" | "ss"
-    )
+/// Longer runs without a word boundary (hashes, base64, minified code) are
+/// not looked up as words
+const MAX_WORD_CHARS: usize = 40;
+
+/// Bounds of the word being typed, carried from one character to the next so
+/// each word is scanned once
+#[derive(Debug, Clone, Default)]
+pub struct WordSpan {
+    start: usize,
+    end: usize,
+    valid: bool,
+    /// Identifier parts and ranks of the word, looked up on first use
+    parts: WordParts,
+}
+
+impl WordSpan {
+    /// Forget the span once the characters it indexes change
+    pub fn reset(&mut self) {
+        self.valid = false;
+        self.parts.reset();
+    }
+
+    /// Look up the parts of the word in `words`, once per word. Words too long
+    /// to be words are left alone.
+    pub fn analyze(&mut self, chars: &[char], words: &WordList) {
+        if self.valid && self.end - self.start <= MAX_WORD_CHARS {
+            words.analyze(&chars[self.start..self.end.min(chars.len())], &mut self.parts);
+        }
+    }
+
+    /// Parts found by [`Self::analyze`]
+    pub fn parts(&self) -> &WordParts {
+        &self.parts
+    }
 }
 
 /// Calculate word position context
+#[derive(Debug, Clone, Copy)]
 pub struct WordContext {
+    /// Index of the first character of the word
+    pub word_start: usize,
     /// Characters since last word boundary
     pub chars_in_word: usize,
     /// Is current char at word start
@@ -83,34 +100,36 @@ pub struct WordContext {
 }
 
 impl WordContext {
-    pub fn analyze(chars: &[char], current_index: usize) -> Self {
+    /// Context at `current_index`, reusing `span` while the index stays in
+    /// (or right after) the word it covers
+    pub fn track(chars: &[char], current_index: usize, span: &mut WordSpan) -> Self {
         let current_char = chars.get(current_index).copied().unwrap_or(' ');
         let prev_char = if current_index > 0 { chars.get(current_index - 1).copied() } else { None };
         let next_char = chars.get(current_index + 1).copied();
 
-        // Count chars since last word boundary
-        let mut chars_in_word = 0;
-        let mut i = current_index;
-        while i > 0 {
-            i -= 1;
-            if is_word_boundary(chars[i]) {
-                break;
+        if !span.valid || current_index < span.start || current_index > span.end {
+            // Scan back to the last word boundary and ahead to the next one
+            let mut start = current_index.min(chars.len());
+            while start > 0 && !is_word_boundary(chars[start - 1]) {
+                start -= 1;
             }
-            chars_in_word += 1;
-        }
-
-        // Estimate word length by looking ahead
-        let mut word_length_estimate = chars_in_word;
-        let mut j = current_index;
-        while j < chars.len() && !is_word_boundary(chars[j]) {
-            word_length_estimate += 1;
-            j += 1;
+            let mut end = current_index;
+            while end < chars.len() && !is_word_boundary(chars[end]) {
+                end += 1;
+            }
+            span.start = start;
+            span.end = end;
+            span.valid = true;
+            span.parts.reset();
         }
+        let chars_in_word = current_index - span.start;
+        let word_length_estimate = span.end - span.start;
 
         let is_word_start = prev_char.map(is_word_boundary).unwrap_or(true) && !is_word_boundary(current_char);
         let is_word_end = next_char.map(is_word_boundary).unwrap_or(true) && !is_word_boundary(current_char);
 
         Self {
+            word_start: span.start,
             chars_in_word,
             is_word_start,
             is_word_end,
@@ -119,20 +138,113 @@ impl WordContext {
     }
 }
 
+/// Why a keystroke was sped up or slowed down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    WordStart,
+    Digraph,
+    Trigraph,
+    HandAlternation,
+    SameHand,
+    Space,
+    Punctuation,
+    Paragraph,
+    Burst,
+    Thinking,
+    Warmup,
+    Fatigue,
+    CommonWord,
+    RareWord,
+    Identifier,
+    Composition,
+    Keyword,
+    Symbol,
+}
+
+impl Reason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::WordStart => "word_start",
+            Self::Digraph => "digraph",
+            Self::Trigraph => "trigraph",
+            Self::HandAlternation => "hand_alternation",
+            Self::SameHand => "same_hand",
+            Self::Space => "space",
+            Self::Punctuation => "punctuation",
+            Self::Paragraph => "paragraph",
+            Self::Burst => "burst",
+            Self::Thinking => "thinking",
+            Self::Warmup => "warmup",
+            Self::Fatigue => "fatigue",
+            Self::CommonWord => "common_word",
+            Self::RareWord => "rare_word",
+            Self::Identifier => "identifier",
+            Self::Composition => "composition",
+            Self::Keyword => "keyword",
+            Self::Symbol => "symbol",
+        }
+    }
+}
+
+/// Milliseconds added by each kind of pause (before scaling and variance)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseBreakdown {
+    pub punctuation_ms: u64,
+    pub paragraph_ms: u64,
+    pub thinking_ms: u64,
+    pub composition_ms: u64,
+}
+
+impl PauseBreakdown {
+    pub fn add(&mut self, other: &PauseBreakdown) {
+        self.punctuation_ms += other.punctuation_ms;
+        self.paragraph_ms += other.paragraph_ms;
+        self.thinking_ms += other.thinking_ms;
+        self.composition_ms += other.composition_ms;
+    }
+}
+
+/// A keystroke delay and what went into it
+#[derive(Debug, Clone, Default)]
+pub struct Delay {
+    pub ms: u64,
+    pub reasons: Vec<Reason>,
+    pub pauses: PauseBreakdown,
+}
+
+/// What the delay calculation knows about the content and language
+pub struct TimingContext<'a> {
+    pub ngrams: &'a NgramTable,
+    pub words: &'a WordList,
+    pub structure: &'a Structure,
+    /// Tokenized source, when typing code
+    pub code: Option<&'a CodeModel>,
+    /// Warmup and fatigue at this point of the session
+    pub fatigue: &'a FatigueState,
+    /// Position in the word being typed
+    pub word: WordContext,
+    /// Parts of that word, when word difficulty is on
+    pub word_parts: &'a WordParts,
+}
+
 /// Calculate delay for a specific character with context (enhanced version)
 pub fn calculate_delay_v2(
     config: &Config,
+    ctx: &TimingContext,
     chars: &[char],
     current_index: usize,
-    total_chars: usize,
-) -> u64 {
+) -> Delay {
     let mut rng = rand::thread_rng();
     let base = base_delay_ms(config.base_wpm);
 
     let current_char = chars[current_index];
     let prev_char = if current_index > 0 { Some(chars[current_index - 1]) } else { None };
 
-    let word_ctx = WordContext::analyze(chars, current_index);
+    let word_ctx = ctx.word;
+    let mut reasons = Vec::new();
+    let mut pauses = PauseBreakdown::default();
 
     // Start with base delay
     let mut delay = base as f64;
@@ -142,6 +254,7 @@ pub fn calculate_delay_v2(
     // Word start hesitation - slight pause when beginning a new word
     if word_ctx.is_word_start {
         delay *= 1.15 + rng.gen::<f64>() * 0.15; // 15-30% slower at word start
+        reasons.push(Reason::WordStart);
     }
 
     // Mid-word momentum - type faster as you flow through a word
@@ -157,12 +270,40 @@ pub fn calculate_delay_v2(
         delay *= momentum;
     }
 
+    // Source code: keywords come from muscle memory, symbols are reaches
+    let token = ctx.code.map(|code| code.token(current_index));
+    match token {
+        Some(Token::Keyword) => {
+            delay *= 0.75;
+            reasons.push(Reason::Keyword);
+        }
+        Some(Token::Symbol) => {
+            delay *= code::symbol_cost(prev_char, current_char);
+            reasons.push(Reason::Symbol);
+        }
+        Some(Token::Indent) => delay *= 0.5,
+        _ => {}
+    }
+    // Comments and strings are typed like prose
+    let in_prose = token.is_none_or(|t| t == Token::Text);
+
+    // Familiar words flow, rare words and identifiers need more attention
+    if config.word_difficulty
+        && !is_word_boundary(current_char)
+        && word_ctx.word_length_estimate <= MAX_WORD_CHARS
+        && token != Some(Token::Keyword)
+    {
+        delay *= ctx.words.difficulty(ctx.word_parts, word_ctx.chars_in_word, &mut reasons);
+    }
+
     // === DIGRAPH AND HAND PATTERNS ===
 
     if let Some(prev) = prev_char {
-        // Common digraphs are typed faster (muscle memory)
-        if is_common_digraph(prev, current_char) {
-            delay *= 0.75 + rng.gen::<f64>() * 0.1; // 15-25% faster
+        // Common n-grams are typed faster (muscle memory), awkward ones slower
+        let before_prev = current_index.checked_sub(2).map(|i| chars[i]);
+        if let Some((stat, trigraph)) = ctx.ngrams.lookup(before_prev, prev, current_char) {
+            delay *= stat.sample(&config.interval_distribution, &mut rng);
+            reasons.push(if trigraph { Reason::Trigraph } else { Reason::Digraph });
         }
 
         // Hand alternation is faster than same-hand sequences
@@ -170,9 +311,11 @@ pub fn calculate_delay_v2(
         let curr_hand = get_hand(current_char);
         if prev_hand != curr_hand && prev_hand < 2 && curr_hand < 2 {
             delay *= 0.88 + rng.gen::<f64>() * 0.08; // 4-12% faster
+            reasons.push(Reason::HandAlternation);
         } else if prev_hand == curr_hand && prev_hand < 2 {
             // Same hand is slightly slower
             delay *= 1.05 + rng.gen::<f64>() * 0.1; // 5-15% slower
+            reasons.push(Reason::SameHand);
         }
     }
 
@@ -181,22 +324,48 @@ pub fn calculate_delay_v2(
     // Space after word - thinking about next word
     if current_char == ' ' {
         delay *= 1.2 + rng.gen::<f64>() * 0.3; // 20-50% slower for spaces
+        reasons.push(Reason::Space);
     }
 
     // After punctuation - longer pause
-    if let Some(prev) = prev_char {
+    if let Some(prev) = prev_char.filter(|_| in_prose) {
         if matches!(prev, '.' | '!' | '?') {
             // End of sentence - longer pause
-            delay += config.punctuation_pause as f64 * (0.8 + rng.gen::<f64>() * 0.4);
+            let pause = config.punctuation_pause as f64 * (0.8 + rng.gen::<f64>() * 0.4);
+            delay += pause;
+            pauses.punctuation_ms += pause as u64;
+            reasons.push(Reason::Punctuation);
         } else if matches!(prev, ',' | ';' | ':') {
             // Mid-sentence punctuation
-            delay += (config.punctuation_pause as f64 * 0.5) * (0.7 + rng.gen::<f64>() * 0.6);
+            let pause = (config.punctuation_pause as f64 * 0.5) * (0.7 + rng.gen::<f64>() * 0.6);
+            delay += pause;
+            pauses.punctuation_ms += pause as u64;
+            reasons.push(Reason::Punctuation);
         }
     }
 
-    // Newlines/paragraphs
-    if current_char == '\n' {
-        delay += config.paragraph_pause as f64 * (0.6 + rng.gen::<f64>() * 0.8);
+    // Newlines/paragraphs (in code, statement pauses take their place)
+    if current_char == '\n' && ctx.code.is_none() {
+        let pause = config.paragraph_pause as f64 * (0.6 + rng.gen::<f64>() * 0.8);
+        delay += pause;
+        pauses.paragraph_ms += pause as u64;
+        reasons.push(Reason::Paragraph);
+    }
+
+    // Composing the next paragraph, sentence or clause (or in code, the next
+    // statement) before starting it
+    let composition = match ctx.code {
+        Some(code) => code.statement_at(current_index + 1),
+        None => ctx.structure.unit_at(current_index + 1).map(|unit| unit.scale()),
+    };
+    if let Some(scale) = composition {
+        if config.composition_pause > 0 {
+            let mean = (config.composition_pause as f64 * scale) as u64;
+            let pause = add_variance(mean, 0.35, &config.interval_distribution);
+            delay += pause as f64;
+            pauses.composition_ms += pause;
+            reasons.push(Reason::Composition);
+        }
     }
 
     // === BURST TYPING ===
@@ -205,6 +374,7 @@ pub fn calculate_delay_v2(
     if config.burst_typing && rng.gen::<f64>() < 0.08 {
         // Burst affects multiple characters, so make it subtle
         delay *= 0.6 + rng.gen::<f64>() * 0.15;
+        reasons.push(Reason::Burst);
     }
 
     // === THINKING PAUSES ===
@@ -219,24 +389,25 @@ pub fn calculate_delay_v2(
     };
 
     if rng.gen::<f64>() < thinking_chance {
-        let thinking = add_variance(config.thinking_pause_duration, 0.4);
+        let thinking = add_variance(config.thinking_pause_duration, 0.4, &config.interval_distribution);
         delay += thinking as f64;
+        pauses.thinking_ms += thinking;
+        reasons.push(Reason::Thinking);
     }
 
     // === WARMUP AND FATIGUE ===
 
-    // Warmup - slower at the very start
-    let warmup_chars = 30;
-    if current_index < warmup_chars {
-        let warmup_factor = 1.0 + 0.35 * (1.0 - (current_index as f64 / warmup_chars as f64)).powi(2);
+    // Slower until warmed up, and increasingly slower the longer the session
+    // has gone on without rest
+    let warmup_factor = ctx.fatigue.warmup_factor(&config.fatigue);
+    if warmup_factor > 1.01 {
         delay *= warmup_factor;
+        reasons.push(Reason::Warmup);
     }
-
-    // Fatigue near the end
-    let progress = current_index as f64 / total_chars.max(1) as f64;
-    if progress > 0.85 {
-        let fatigue_factor = 1.0 + 0.15 * ((progress - 0.85) / 0.15);
+    let fatigue_factor = ctx.fatigue.fatigue_factor(&config.fatigue);
+    if fatigue_factor > 1.01 {
         delay *= fatigue_factor;
+        reasons.push(Reason::Fatigue);
     }
 
     // === FINAL VARIANCE ===
@@ -245,9 +416,13 @@ pub fn calculate_delay_v2(
     let final_delay = delay * (0.9 + rng.gen::<f64>() * 0.2);
 
     // Apply configured variance
-    let with_variance = add_variance(final_delay as u64, config.wpm_variance * 0.5);
+    let with_variance = add_variance(final_delay as u64, config.wpm_variance * 0.5, &config.interval_distribution);
 
-    with_variance.max(8) // Minimum 8ms between keystrokes
+    Delay {
+        ms: with_variance.max(8), // Minimum 8ms between keystrokes
+        reasons,
+        pauses,
+    }
 }
 
 /// Legacy function for backward compatibility
@@ -260,7 +435,7 @@ pub fn calculate_delay(
 ) -> u64 {
     let mut rng = rand::thread_rng();
     let base = base_delay_ms(config.base_wpm);
-    let mut delay = add_variance(base, config.wpm_variance);
+    let mut delay = add_variance(base, config.wpm_variance, &config.interval_distribution);
 
     // Warmup period - type slower at the start
     let warmup_chars = 20;
@@ -284,7 +459,7 @@ pub fn calculate_delay(
     // Random thinking pause
     if rng.gen::<f64>() < config.thinking_pause_chance {
         // Add variance to thinking pause too
-        let thinking = add_variance(config.thinking_pause_duration, 0.3);
+        let thinking = add_variance(config.thinking_pause_duration, 0.3, &config.interval_distribution);
         delay += thinking;
     }
 
@@ -307,7 +482,27 @@ pub fn calculate_delay(
 pub fn backspace_delay(config: &Config) -> u64 {
     let base = base_delay_ms(config.base_wpm);
     let faster = (base as f64 * 0.7) as u64; // 30% faster
-    add_variance(faster, config.wpm_variance * 0.5).max(10)
+    add_variance(faster, config.wpm_variance * 0.5, &config.interval_distribution)
+}
+
+/// How long to hold a key that is followed by `next_delay_ms` before the next
+/// press. Without rollover the key always comes up before the next one.
+pub fn key_hold(config: &Config, next_delay_ms: u64) -> u64 {
+    if config.key_hold_ms == 0 {
+        return 0;
+    }
+    let hold = add_variance(config.key_hold_ms, 0.25, &config.interval_distribution);
+    if config.rollover {
+        hold
+    } else {
+        hold.min(next_delay_ms * 4 / 5).max(1)
+    }
+}
+
+/// Time to reach for and press an editor shortcut, and get back to the keys
+pub fn shortcut_delay(config: &Config) -> u64 {
+    let base = base_delay_ms(config.base_wpm);
+    add_variance(base * 3, config.wpm_variance, &config.interval_distribution)
 }
 
 /// Calculate delay before noticing a mistake
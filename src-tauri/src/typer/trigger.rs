@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What has to happen before typing starts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum StartTrigger {
+    /// Count down; `None` uses `countdown_seconds`
+    Countdown {
+        #[serde(default)]
+        seconds: Option<u32>,
+    },
+    /// Wait for the start/stop hotkey or a `continue_queue` command
+    Hotkey,
+    /// Wait until a window whose title contains `title` (case-insensitive) is focused
+    #[serde(rename_all = "camelCase")]
+    WindowFocus { title: String },
+    /// Start at a wall-clock time (Unix epoch milliseconds)
+    #[serde(rename_all = "camelCase")]
+    At { unix_ms: u64 },
+}
+
+impl Default for StartTrigger {
+    fn default() -> Self {
+        Self::Countdown { seconds: None }
+    }
+}
+
+impl StartTrigger {
+    /// Whether `title` satisfies a [`StartTrigger::WindowFocus`] pattern
+    pub fn title_matches(pattern: &str, title: &str) -> bool {
+        title.to_lowercase().contains(&pattern.to_lowercase())
+    }
+}
+
+/// Milliseconds until `unix_ms` (zero if it has passed)
+pub fn millis_until(unix_ms: u64) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    unix_ms.saturating_sub(now)
+}
@@ -0,0 +1,150 @@
+use serde::Serialize;
+
+/// The window that currently has keyboard focus
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ActiveWindow {
+    pub id: u64,
+    pub title: String,
+}
+
+/// Queries the focused window. Holds a connection to the window system so it
+/// can be polled cheaply.
+pub struct WindowProbe {
+    #[cfg(target_os = "linux")]
+    x11: x11::Probe,
+}
+
+impl WindowProbe {
+    #[cfg(target_os = "linux")]
+    pub fn new() -> Result<Self, String> {
+        Ok(Self {
+            x11: x11::Probe::connect()?,
+        })
+    }
+
+    #[cfg(windows)]
+    pub fn new() -> Result<Self, String> {
+        Ok(Self {})
+    }
+
+    #[cfg(not(any(target_os = "linux", windows)))]
+    pub fn new() -> Result<Self, String> {
+        Err("Window detection is not supported on this platform".to_string())
+    }
+
+    /// The focused window, if any
+    pub fn active_window(&self) -> Option<ActiveWindow> {
+        #[cfg(target_os = "linux")]
+        return self.x11.active_window();
+
+        #[cfg(windows)]
+        return win32::active_window();
+
+        #[cfg(not(any(target_os = "linux", windows)))]
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use super::ActiveWindow;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt, Window};
+    use x11rb::rust_connection::RustConnection;
+
+    pub struct Probe {
+        conn: RustConnection,
+        root: Window,
+        net_active_window: Atom,
+        net_wm_name: Atom,
+        utf8_string: Atom,
+    }
+
+    impl Probe {
+        pub fn connect() -> Result<Self, String> {
+            let (conn, screen) = x11rb::connect(None)
+                .map_err(|e| format!("Failed to connect to the X server: {}", e))?;
+            let root = conn.setup().roots[screen].root;
+            let atom = |name: &[u8]| -> Result<Atom, String> {
+                conn.intern_atom(false, name)
+                    .map_err(|e| e.to_string())?
+                    .reply()
+                    .map(|r| r.atom)
+                    .map_err(|e| e.to_string())
+            };
+            let net_active_window = atom(b"_NET_ACTIVE_WINDOW")?;
+            let net_wm_name = atom(b"_NET_WM_NAME")?;
+            let utf8_string = atom(b"UTF8_STRING")?;
+
+            Ok(Self {
+                conn,
+                root,
+                net_active_window,
+                net_wm_name,
+                utf8_string,
+            })
+        }
+
+        /// Read the window manager's `_NET_ACTIVE_WINDOW` property
+        pub fn active_window(&self) -> Option<ActiveWindow> {
+            let reply = self
+                .conn
+                .get_property(false, self.root, self.net_active_window, AtomEnum::WINDOW, 0, 1)
+                .ok()?
+                .reply()
+                .ok()?;
+            let window = reply.value32()?.next()?;
+            if window == 0 {
+                return None;
+            }
+
+            Some(ActiveWindow {
+                id: window as u64,
+                title: self.title(window).unwrap_or_default(),
+            })
+        }
+
+        fn title(&self, window: Window) -> Option<String> {
+            let reply = self
+                .conn
+                .get_property(false, window, self.net_wm_name, self.utf8_string, 0, 1024)
+                .ok()?
+                .reply()
+                .ok()?;
+            if !reply.value.is_empty() {
+                return Some(String::from_utf8_lossy(&reply.value).into_owned());
+            }
+
+            // Fall back to the legacy ICCCM name
+            let reply = self
+                .conn
+                .get_property(false, window, AtomEnum::WM_NAME, AtomEnum::STRING, 0, 1024)
+                .ok()?
+                .reply()
+                .ok()?;
+            Some(reply.value.iter().map(|&b| b as char).collect())
+        }
+    }
+}
+
+#[cfg(windows)]
+mod win32 {
+    use super::ActiveWindow;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW};
+
+    pub fn active_window() -> Option<ActiveWindow> {
+        // SAFETY: both calls only read window state; the buffer outlives the call
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_null() {
+                return None;
+            }
+            let mut buf = [0u16; 512];
+            let len = GetWindowTextW(hwnd, buf.as_mut_ptr(), buf.len() as i32);
+            Some(ActiveWindow {
+                id: hwnd as u64,
+                title: String::from_utf16_lossy(&buf[..len.max(0) as usize]),
+            })
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::timing::Reason;
+
+/// Bundled word lists, by language code: one word per line, most frequent first
+const BUNDLED: &[(&str, &str)] = &[
+    ("en", include_str!("../../data/words/en.txt")),
+    ("de", include_str!("../../data/words/de.txt")),
+];
+
+/// Language used when the configured one has no bundled list
+const FALLBACK_LANGUAGE: &str = "en";
+
+/// Words ranked above this are typed from muscle memory
+const COMMON_RANK: usize = 100;
+
+/// A word missing from a list at least this long is outside everyday
+/// vocabulary. Shorter lists leave too much out to tell, so words they miss
+/// are typed at the normal pace.
+const RARE_RANK: usize = 10_000;
+
+/// Slowdown at each new part of an identifier (case change, underscore)
+const IDENTIFIER_PART_FACTOR: f64 = 1.2;
+
+/// Word frequency ranks for one language
+#[derive(Debug, Default)]
+pub struct WordList {
+    ranks: HashMap<String, usize>,
+}
+
+impl WordList {
+    /// Parse a list with one word per line, most frequent first, `#` comments
+    pub fn parse(text: &str) -> Self {
+        let mut ranks = HashMap::new();
+        let words = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        for word in words {
+            let rank = ranks.len();
+            ranks.entry(word.to_lowercase()).or_insert(rank);
+        }
+        Self { ranks }
+    }
+
+    /// Bundled list for `language` (falling back to English)
+    pub fn bundled(language: &str) -> Self {
+        let text = BUNDLED
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(language))
+            .or_else(|| BUNDLED.iter().find(|(code, _)| *code == FALLBACK_LANGUAGE))
+            .map(|(_, text)| *text)
+            .unwrap_or_default();
+        Self::parse(text)
+    }
+
+    /// Frequency rank of `word` (0 is the most common), ignoring case
+    pub fn rank(&self, word: &str) -> Option<usize> {
+        self.ranks.get(&word.to_lowercase()).copied()
+    }
+
+    /// Split `word` into `parts` and look up their ranks, unless that was
+    /// already done for this word
+    pub fn analyze(&self, word: &[char], parts: &mut WordParts) {
+        if parts.ready {
+            return;
+        }
+        let WordParts { parts, lowercase, ready } = parts;
+        parts.clear();
+        identifier_parts(word, |range| {
+            lowercase.clear();
+            lowercase.extend(word[range.clone()].iter().flat_map(|c| c.to_lowercase()));
+            parts.push((range, self.ranks.get(lowercase.as_str()).copied()));
+        });
+        *ready = true;
+    }
+
+    /// Delay multiplier for typing position `pos` of the word split into
+    /// `parts`, pushing what shaped it to `reasons`. Common words are faster,
+    /// identifiers (camelCase, snake_case) slower, and long words missing
+    /// from a list of at least [`RARE_RANK`] words slower too.
+    pub fn difficulty(&self, parts: &WordParts, pos: usize, reasons: &mut Vec<Reason>) -> f64 {
+        let parts = &parts.parts;
+        let Some(part) = parts.iter().position(|(p, _)| p.contains(&pos)) else {
+            // Separator inside an identifier (e.g. the underscore reach)
+            reasons.push(Reason::Identifier);
+            return IDENTIFIER_PART_FACTOR;
+        };
+
+        let (range, rank) = parts[part].clone();
+        let mut factor = match rank {
+            Some(rank) if rank < COMMON_RANK => {
+                if parts.len() == 1 {
+                    reasons.push(Reason::CommonWord);
+                }
+                0.82 + 0.08 * rank as f64 / COMMON_RANK as f64
+            }
+            // Known but less common: fades from 0.9 to 1.0 over the list
+            Some(rank) => {
+                let spread = (self.ranks.len().max(COMMON_RANK + 1) as f64 / COMMON_RANK as f64).ln();
+                0.9 + 0.1 * ((rank as f64 / COMMON_RANK as f64).ln() / spread).min(1.0)
+            }
+            // Short unknown words are mostly abbreviations
+            None if range.len() <= 3 || self.ranks.len() < RARE_RANK => 1.0,
+            None => {
+                reasons.push(Reason::RareWord);
+                (1.05 + 0.03 * (range.len() - 3) as f64).min(1.35)
+            }
+        };
+
+        if parts.len() > 1 {
+            if !reasons.contains(&Reason::Identifier) {
+                reasons.push(Reason::Identifier);
+            }
+            if part > 0 && pos == range.start {
+                factor *= IDENTIFIER_PART_FACTOR;
+            }
+        }
+        factor
+    }
+}
+
+/// A word split into identifier parts, with the frequency rank of each.
+/// Worked out once per word rather than per keystroke.
+#[derive(Debug, Clone, Default)]
+pub struct WordParts {
+    parts: Vec<(Range<usize>, Option<usize>)>,
+    /// Lowercased part being looked up, reused between lookups
+    lowercase: String,
+    ready: bool,
+}
+
+impl WordParts {
+    /// Forget the parts once the word changes
+    pub fn reset(&mut self) {
+        self.ready = false;
+    }
+}
+
+/// Split a word into its parts, passing each to `part`: at underscores and
+/// other separators, at lower-to-upper case changes ("parseHttpRequest"),
+/// before the last capital of an acronym ("HTTPServer") and between letters
+/// and digits
+fn identifier_parts(word: &[char], mut part: impl FnMut(Range<usize>)) {
+    let mut start: Option<usize> = None;
+
+    for (i, &c) in word.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if let Some(s) = start.take() {
+                part(s..i);
+            }
+            continue;
+        }
+        let Some(s) = start else {
+            start = Some(i);
+            continue;
+        };
+        let prev = word[i - 1];
+        let next_lower = word.get(i + 1).is_some_and(|n| n.is_lowercase());
+        let split = (prev.is_lowercase() && c.is_uppercase())
+            || (prev.is_uppercase() && c.is_uppercase() && next_lower)
+            || (prev.is_alphabetic() != c.is_alphabetic());
+        if split {
+            part(s..i);
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        part(s..word.len());
+    }
+}
+
+/// Loaded lists by language
+static LISTS: Lazy<Mutex<HashMap<String, Arc<WordList>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The shared word list for `language`
+pub fn list_for(language: &str) -> Arc<WordList> {
+    LISTS
+        .lock()
+        .entry(language.to_ascii_lowercase())
+        .or_insert_with(|| Arc::new(WordList::bundled(language)))
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyzed(list: &WordList, word: &str) -> (Vec<char>, WordParts) {
+        let chars: Vec<char> = word.chars().collect();
+        let mut parts = WordParts::default();
+        list.analyze(&chars, &mut parts);
+        (chars, parts)
+    }
+
+    fn split(word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut parts = Vec::new();
+        identifier_parts(&chars, |range| parts.push(chars[range].iter().collect()));
+        parts
+    }
+
+    #[test]
+    fn ranks_ignore_case_comments_and_repeats() {
+        let list = WordList::parse("# header\nthe\n\nOf\nthe\nand\n");
+        assert_eq!(list.rank("The"), Some(0));
+        assert_eq!(list.rank("of"), Some(1));
+        assert_eq!(list.rank("and"), Some(2));
+        assert_eq!(list.rank("header"), None);
+    }
+
+    #[test]
+    fn bundled_lists_fall_back_to_english() {
+        assert_eq!(WordList::bundled("en").rank("the"), Some(0));
+        assert!(WordList::bundled("DE").rank("und").is_some());
+        assert_eq!(WordList::bundled("xx").rank("the"), Some(0));
+    }
+
+    #[test]
+    fn identifiers_split_at_case_separators_and_digits() {
+        assert_eq!(split("parseHttpRequest"), ["parse", "Http", "Request"]);
+        assert_eq!(split("HTTPServer"), ["HTTP", "Server"]);
+        assert_eq!(split("max_file_mb"), ["max", "file", "mb"]);
+        assert_eq!(split("utf8"), ["utf", "8"]);
+        assert_eq!(split("word"), ["word"]);
+    }
+
+    #[test]
+    fn common_words_are_faster() {
+        let list = WordList::bundled("en");
+        let (_, parts) = analyzed(&list, "the");
+        let mut reasons = Vec::new();
+        assert!(list.difficulty(&parts, 0, &mut reasons) < 1.0);
+        assert_eq!(reasons, [Reason::CommonWord]);
+    }
+
+    #[test]
+    fn unknown_words_are_neutral_with_a_short_list() {
+        let list = WordList::bundled("en");
+        let (_, parts) = analyzed(&list, "photosynthesis");
+        let mut reasons = Vec::new();
+        assert_eq!(list.difficulty(&parts, 4, &mut reasons), 1.0);
+        assert!(reasons.is_empty());
+    }
+
+    #[test]
+    fn unknown_words_are_rare_with_a_full_list() {
+        let words: String = (0..RARE_RANK).map(|i| format!("w{}\n", i)).collect();
+        let list = WordList::parse(&words);
+        let (_, parts) = analyzed(&list, "photosynthesis");
+        let mut reasons = Vec::new();
+        assert!(list.difficulty(&parts, 4, &mut reasons) > 1.0);
+        assert_eq!(reasons, [Reason::RareWord]);
+    }
+
+    #[test]
+    fn identifier_parts_slow_down_at_their_start() {
+        let list = WordList::bundled("en");
+        let (_, parts) = analyzed(&list, "getValue");
+        let mut reasons = Vec::new();
+        let inside = list.difficulty(&parts, 4, &mut reasons);
+        let start = list.difficulty(&parts, 3, &mut reasons);
+        assert!(reasons.contains(&Reason::Identifier));
+        assert!((start / inside - IDENTIFIER_PART_FACTOR).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parts_are_kept_until_reset() {
+        let list = WordList::bundled("en");
+        let (_, mut parts) = analyzed(&list, "the");
+        let other: Vec<char> = "getValue".chars().collect();
+        list.analyze(&other, &mut parts);
+        assert_eq!(parts.parts.len(), 1);
+        parts.reset();
+        list.analyze(&other, &mut parts);
+        assert_eq!(parts.parts.len(), 2);
+    }
+}
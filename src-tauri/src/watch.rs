@@ -0,0 +1,262 @@
+//! Reloading a file loaded with `load_file` when it changes on disk.
+
+use crate::content::{self, diff};
+use crate::typer::events::EventSink;
+use crate::{emit_queue, engine, file_info, read_file};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Quiet time after a change before the file is read (editors often write
+/// in several steps)
+const DEBOUNCE: Duration = Duration::from_millis(250);
+/// How often a change held back by a run is retried
+const RETRY: Duration = Duration::from_secs(1);
+
+struct Watched {
+    path: PathBuf,
+    /// Cleared when the watch is replaced or stopped
+    active: Arc<AtomicBool>,
+    _watcher: RecommendedWatcher,
+}
+
+static WATCHED: Lazy<Mutex<Option<Watched>>> = Lazy::new(|| Mutex::new(None));
+
+/// File being watched, if any
+pub fn watched_path() -> Option<PathBuf> {
+    WATCHED.lock().as_ref().map(|w| w.path.clone())
+}
+
+/// Watch `path`, whose content was loaded as `text`, replacing any previous
+/// watch
+pub fn start<S: EventSink + Clone>(path: &Path, text: Arc<str>, events: S) -> Result<(), String> {
+    stop();
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+    // Watch the directory so saves that replace the file are seen too
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+
+    let active = Arc::new(AtomicBool::new(true));
+    let watch = WatchLoop {
+        path: path.clone(),
+        text,
+        held_back: false,
+        failed: false,
+        active: active.clone(),
+    };
+    std::thread::spawn(move || watch.run(rx, events));
+
+    *WATCHED.lock() = Some(Watched {
+        path,
+        active,
+        _watcher: watcher,
+    });
+    Ok(())
+}
+
+/// Stop watching
+pub fn stop() {
+    if let Some(watched) = WATCHED.lock().take() {
+        watched.active.store(false, Ordering::SeqCst);
+    }
+}
+
+struct WatchLoop {
+    path: PathBuf,
+    /// Content as last loaded
+    text: Arc<str>,
+    /// A change is waiting for the current run to end
+    held_back: bool,
+    /// The last reload failed (reported once until one succeeds)
+    failed: bool,
+    active: Arc<AtomicBool>,
+}
+
+impl WatchLoop {
+    fn run(mut self, rx: Receiver<notify::Result<Event>>, events: impl EventSink) {
+        let mut pending = false;
+        loop {
+            let changed = match rx.recv_timeout(RETRY) {
+                Ok(Ok(event)) => self.concerns(&event),
+                Ok(Err(_)) | Err(RecvTimeoutError::Timeout) => false,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+            if !self.active.load(Ordering::SeqCst) {
+                return;
+            }
+            if changed {
+                while rx.recv_timeout(DEBOUNCE).is_ok() {}
+                pending = true;
+            }
+            if pending {
+                pending = !self.reload(&events);
+            }
+        }
+    }
+
+    fn concerns(&self, event: &Event) -> bool {
+        matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Any
+        ) && event.paths.iter().any(|p| p.file_name() == self.path.file_name())
+    }
+
+    /// Load the new version, returning false if it has to wait for the
+    /// current run to end
+    fn reload(&mut self, events: &impl EventSink) -> bool {
+        // A held-back change was already reported; read the file again once
+        // the run is over rather than on every retry
+        let typing = engine().get_status().is_active();
+        if typing && self.held_back {
+            return false;
+        }
+
+        let path = self.path.to_string_lossy();
+        let (name, extracted) = match read_file(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                // Removed or mid-save; a later event brings it back
+                if !self.failed {
+                    self.failed = true;
+                    engine().emit(
+                        events,
+                        "content-changed",
+                        serde_json::json!({ "applied": false, "message": e }),
+                    );
+                }
+                return true;
+            }
+        };
+        self.failed = false;
+        let document = content::prepare(name, extracted.text, &engine().get_config());
+        if document.text == self.text {
+            return true;
+        }
+        let summary = diff::summarize(&self.text, &document.text);
+
+        if !self.active.load(Ordering::SeqCst) {
+            return true;
+        }
+
+        // A run that started since the check above makes the engine refuse
+        let info = file_info(&document, extracted.format, extracted.encoding);
+        let text = document.text.clone();
+        if typing || engine().set_content(document).is_err() {
+            if !self.held_back {
+                self.held_back = true;
+                engine().emit(
+                    events,
+                    "content-changed",
+                    serde_json::json!({
+                        "applied": false,
+                        "message": "Cannot change the content while typing; it is reloaded when the run ends",
+                        "diff": summary,
+                    }),
+                );
+            }
+            return false;
+        }
+        self.text = text;
+        self.held_back = false;
+        engine().emit(
+            events,
+            "content-changed",
+            serde_json::json!({ "applied": true, "diff": summary, "file": info }),
+        );
+        emit_queue(events);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TypingStatus;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<serde_json::Value>>);
+
+    impl EventSink for Recorder {
+        fn emit_event(&self, event: &str, payload: serde_json::Value) {
+            if event == "content-changed" {
+                self.0.lock().push(payload);
+            }
+        }
+    }
+
+    impl Recorder {
+        fn take(&self) -> Vec<serde_json::Value> {
+            std::mem::take(&mut *self.0.lock())
+        }
+    }
+
+    fn queued_text() -> String {
+        engine().get_queue()[0].content.to_string()
+    }
+
+    #[test]
+    fn reload_applies_holds_back_during_a_run_and_reports_errors_once() {
+        let path = std::env::temp_dir().join(format!("ghostkeys-watch-{}.txt", std::process::id()));
+        std::fs::write(&path, "one\n").unwrap();
+        let events = Recorder::default();
+        let mut watch = WatchLoop {
+            path: path.clone(),
+            text: "one\n".into(),
+            held_back: false,
+            failed: false,
+            active: Arc::new(AtomicBool::new(true)),
+        };
+
+        // Unchanged content is not reported
+        assert!(watch.reload(&events));
+        assert!(events.take().is_empty());
+
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        assert!(watch.reload(&events));
+        let changed = events.take();
+        assert_eq!(changed[0]["applied"], true);
+        assert_eq!(changed[0]["diff"]["linesAdded"], 1);
+        assert_eq!(queued_text(), "one\ntwo\n");
+
+        // During a run the change waits, and is not read again on retries
+        engine().set_status(TypingStatus::Typing, &events);
+        std::fs::write(&path, "three\n").unwrap();
+        assert!(!watch.reload(&events));
+        assert_eq!(events.take()[0]["applied"], false);
+        std::fs::remove_file(&path).unwrap();
+        assert!(!watch.reload(&events));
+        assert!(events.take().is_empty());
+        assert_eq!(queued_text(), "one\ntwo\n");
+
+        // Once the run ends the file is read again
+        engine().set_status(TypingStatus::Ready, &events);
+        std::fs::write(&path, "three\n").unwrap();
+        assert!(watch.reload(&events));
+        assert_eq!(events.take()[0]["applied"], true);
+        assert_eq!(queued_text(), "three\n");
+
+        // A file that cannot be read is reported once
+        std::fs::remove_file(&path).unwrap();
+        assert!(watch.reload(&events));
+        assert!(watch.reload(&events));
+        let failed = events.take();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0]["applied"], false);
+        assert!(failed[0].get("diff").is_none());
+
+        engine().set_status(TypingStatus::Idle, &events);
+    }
+}
@@ -0,0 +1,67 @@
+//! PDF extraction runs in a helper process started from the binary itself,
+//! so it is tested through the CLI rather than from the library's unit tests.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// A one-page PDF showing `text`, with a correct cross-reference table
+fn pdf(text: &str) -> Vec<u8> {
+    let content = format!("BT /F1 12 Tf 20 100 Td ({}) Tj ET", text);
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 200] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>".to_string(),
+        format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).bytes());
+    }
+    let xref = out.len();
+    out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        out.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    out.extend(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).bytes());
+    out
+}
+
+fn preview(name: &str, bytes: &[u8]) -> Output {
+    let path: PathBuf = std::env::temp_dir().join(format!("ghostkeys-{}-{}.pdf", name, std::process::id()));
+    std::fs::write(&path, bytes).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_ghostkeys-cli"))
+        .args(["preview", "--file"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    output
+}
+
+#[test]
+fn text_is_extracted() {
+    let output = preview("valid", &pdf("Hello PDF"));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Hello PDF"));
+}
+
+#[test]
+fn corrupt_pdfs_are_errors_not_crashes() {
+    let mut garbage = b"%PDF-1.7\n".to_vec();
+    garbage.extend((0..4096u32).map(|i| (i * 7919 % 251) as u8));
+    let valid = pdf("Hello PDF");
+    let corrupt: [(&str, &[u8]); 3] = [
+        ("truncated", b"%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R"),
+        ("garbage", &garbage),
+        ("cut", &valid[..valid.len() / 2]),
+    ];
+    for (name, bytes) in corrupt {
+        let output = preview(name, bytes);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(1), "{}: {}", name, stderr);
+        assert!(stderr.contains("Failed to read PDF"), "{}: {}", name, stderr);
+    }
+}
@@ -4,13 +4,26 @@ import { TypingControls } from "./components/TypingControls";
 import { SettingsPanel } from "./components/SettingsPanel";
 import { StatusBar } from "./components/StatusBar";
 import { CountdownOverlay } from "./components/CountdownOverlay";
+import { VariablesPrompt } from "./components/VariablesPrompt";
 import { useTypingState } from "./hooks/useTypingState";
 import { useConfig } from "./hooks/useConfig";
 import "./styles/globals.css";
 
 function App() {
-  const { state, countdown, loadContent, updateContent, start, stop, pause, resume } =
-    useTypingState();
+  const {
+    state,
+    countdown,
+    missingVariables,
+    loadContent,
+    updateContent,
+    start,
+    startWithVariables,
+    cancelVariables,
+    typeClipboard,
+    stop,
+    pause,
+    resume,
+  } = useTypingState();
   const { config, updateConfig, resetConfig } = useConfig();
 
   const isTypingOrCountdown =
@@ -43,6 +56,7 @@ function App() {
             <TextEditor
               content={state.content}
               fileName={state.fileName}
+              truncated={state.contentTruncated}
               onContentChange={updateContent}
               disabled={isTypingOrCountdown}
             />
@@ -68,6 +82,7 @@ function App() {
             onStop={stop}
             onPause={pause}
             onResume={resume}
+            onTypeClipboard={typeClipboard}
             hasFile={!!state.fileName}
           />
         </section>
@@ -93,6 +108,13 @@ function App() {
         countdown={countdown}
         visible={state.status === "countdown"}
       />
+
+      {/* Template variables asked for before starting */}
+      <VariablesPrompt
+        names={missingVariables}
+        onSubmit={startWithVariables}
+        onCancel={cancelVariables}
+      />
     </div>
   );
 }
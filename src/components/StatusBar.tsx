@@ -24,6 +24,8 @@ function getStatusInfo(status: TypingStatus): {
       return { label: "Typing", color: "bg-accent-success", pulse: true };
     case "paused":
       return { label: "Paused", color: "bg-accent-warning", pulse: false };
+    case "onBreak":
+      return { label: "On break", color: "bg-accent-info", pulse: false };
     case "done":
       return { label: "Done", color: "bg-accent-success", pulse: false };
     case "error":
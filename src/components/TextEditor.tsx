@@ -3,6 +3,8 @@ import { useState, useCallback, useEffect, useMemo } from "react";
 interface TextEditorProps {
   content: string | null;
   fileName: string | null;
+  // Content is a preview of a longer document and cannot be edited
+  truncated?: boolean;
   onContentChange: (content: string) => void;
   disabled?: boolean;
 }
@@ -10,6 +12,7 @@ interface TextEditorProps {
 export function TextEditor({
   content,
   fileName,
+  truncated,
   onContentChange,
   disabled,
 }: TextEditorProps) {
@@ -88,6 +91,7 @@ export function TextEditor({
         value={localContent}
         onChange={handleChange}
         disabled={disabled}
+        readOnly={truncated}
         placeholder="Your text will appear here..."
         className={`
           w-full h-48 p-4 rounded-lg resize-none
@@ -99,8 +103,15 @@ export function TextEditor({
         `}
       />
 
+      {truncated && (
+        <p className="text-xs text-ghost-500">
+          Preview of the first {localContent.length.toLocaleString()} characters.
+          Edit the file itself to change it.
+        </p>
+      )}
+
       {/* Action buttons (only show when editing) */}
-      {isEditing && !disabled && (
+      {isEditing && !disabled && !truncated && (
         <div className="flex items-center justify-end gap-2">
           <button
             onClick={handleReset}
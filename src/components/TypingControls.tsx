@@ -6,6 +6,7 @@ interface TypingControlsProps {
   onStop: () => void;
   onPause: () => void;
   onResume: () => void;
+  onTypeClipboard: () => void;
   hasFile: boolean;
 }
 
@@ -15,6 +16,7 @@ export function TypingControls({
   onStop,
   onPause,
   onResume,
+  onTypeClipboard,
   hasFile,
 }: TypingControlsProps) {
   const isTyping = status === "typing" || status === "countdown";
@@ -54,6 +56,23 @@ export function TypingControls({
         </button>
       )}
 
+      {/* Type the clipboard right away */}
+      {!isTyping && !isPaused && (
+        <button
+          onClick={onTypeClipboard}
+          title="Load the clipboard text and start typing it"
+          className="py-3 px-6 rounded-lg font-medium transition-all
+                     bg-ghost-700 hover:bg-ghost-600 text-ghost-100
+                     flex items-center justify-center gap-2"
+        >
+          <svg className="w-5 h-5" fill="none" stroke="currentColor" strokeWidth={2} viewBox="0 0 24 24">
+            <rect x="8" y="3" width="8" height="4" rx="1" />
+            <path d="M16 5h2a2 2 0 012 2v12a2 2 0 01-2 2H6a2 2 0 01-2-2V7a2 2 0 012-2h2" />
+          </svg>
+          Clipboard
+        </button>
+      )}
+
       {/* Pause/Resume Button */}
       {(isTyping || isPaused) && (
         <button
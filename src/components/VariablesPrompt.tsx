@@ -0,0 +1,65 @@
+import { useEffect, useState } from "react";
+
+interface VariablesPromptProps {
+  names: string[];
+  onSubmit: (values: Record<string, string>) => void;
+  onCancel: () => void;
+}
+
+export function VariablesPrompt({ names, onSubmit, onCancel }: VariablesPromptProps) {
+  const [values, setValues] = useState<Record<string, string>>({});
+
+  useEffect(() => {
+    setValues({});
+  }, [names]);
+
+  if (names.length === 0) return null;
+
+  const handleSubmit = (e: React.FormEvent) => {
+    e.preventDefault();
+    onSubmit(Object.fromEntries(names.map((name) => [name, values[name] ?? ""])));
+  };
+
+  return (
+    <div className="fixed inset-0 bg-ghost-950/95 flex items-center justify-center z-50">
+      <form onSubmit={handleSubmit} className="w-full max-w-md p-6 space-y-4">
+        <div>
+          <h2 className="text-lg font-medium text-ghost-100">Template variables</h2>
+          <p className="text-ghost-400 text-sm mt-1">
+            Fill in the placeholders before typing starts.
+          </p>
+        </div>
+
+        {names.map((name, i) => (
+          <label key={name} className="block space-y-1">
+            <span className="text-sm text-ghost-300 font-mono">{`{{${name}}}`}</span>
+            <input
+              type="text"
+              autoFocus={i === 0}
+              value={values[name] ?? ""}
+              onChange={(e) => setValues((prev) => ({ ...prev, [name]: e.target.value }))}
+              className="w-full px-3 py-2 rounded-lg bg-ghost-900 border border-ghost-700
+                         text-ghost-100 focus:outline-none focus:border-accent-primary"
+            />
+          </label>
+        ))}
+
+        <div className="flex gap-3 pt-2">
+          <button
+            type="button"
+            onClick={onCancel}
+            className="flex-1 py-2 px-4 rounded-lg bg-ghost-700 hover:bg-ghost-600 text-ghost-100"
+          >
+            Cancel
+          </button>
+          <button
+            type="submit"
+            className="flex-1 py-2 px-4 rounded-lg bg-accent-primary hover:bg-accent-primary/80 text-white"
+          >
+            Start Typing
+          </button>
+        </div>
+      </form>
+    </div>
+  );
+}
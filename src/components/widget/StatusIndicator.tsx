@@ -17,6 +17,8 @@ export function StatusIndicator({ status }: StatusIndicatorProps) {
         return { color: "bg-accent-success", label: "Active", pulse: true };
       case "paused":
         return { color: "bg-accent-warning", label: "Paused", pulse: false };
+      case "onBreak":
+        return { color: "bg-accent-info", label: "Break", pulse: false };
       case "done":
         return { color: "bg-accent-success", label: "Done", pulse: false };
       case "error":
@@ -1,6 +1,12 @@
 import { useState, useEffect, useCallback } from "react";
 import { listen } from "@tauri-apps/api/event";
-import type { TypingState, TypingStatus, TypingProgress } from "../lib/types";
+import type {
+  ContentChanged,
+  FileInfo,
+  TypingState,
+  TypingStatus,
+  TypingProgress,
+} from "../lib/types";
 import * as commands from "../lib/commands";
 
 const initialState: TypingState = {
@@ -10,12 +16,15 @@ const initialState: TypingState = {
   percent: 0,
   fileName: null,
   content: null,
+  contentTruncated: false,
   errorMessage: null,
 };
 
 export function useTypingState() {
   const [state, setState] = useState<TypingState>(initialState);
   const [countdown, setCountdown] = useState<number>(0);
+  // Template variables to ask for before starting
+  const [missingVariables, setMissingVariables] = useState<string[]>([]);
 
   // Listen for backend events
   useEffect(() => {
@@ -56,11 +65,51 @@ export function useTypingState() {
       }
     );
 
+    // Clipboard text loaded from the tray or a hotkey
+    const unlistenContent = listen<FileInfo>("content-loaded", (event) => {
+      setState((prev) => ({
+        ...prev,
+        fileName: event.payload.name,
+        content: event.payload.content,
+        contentTruncated: event.payload.truncated,
+        totalChars: event.payload.charCount,
+        currentChar: 0,
+        percent: 0,
+        errorMessage: null,
+      }));
+    });
+
+    // Watched file changed on disk
+    const unlistenChanged = listen<ContentChanged>("content-changed", (event) => {
+      const { file } = event.payload;
+      if (!file) return;
+      setState((prev) => ({
+        ...prev,
+        fileName: file.name,
+        content: file.content,
+        contentTruncated: file.truncated,
+        totalChars: file.charCount,
+        currentChar: 0,
+        percent: 0,
+      }));
+    });
+
+    // Started from the tray or a hotkey with variables still missing
+    const unlistenVariables = listen<{ names: string[] }>(
+      "variables-required",
+      (event) => {
+        setMissingVariables(event.payload.names);
+      }
+    );
+
     return () => {
       unlistenProgress.then((fn) => fn());
       unlistenState.then((fn) => fn());
       unlistenError.then((fn) => fn());
       unlistenCountdown.then((fn) => fn());
+      unlistenVariables.then((fn) => fn());
+      unlistenContent.then((fn) => fn());
+      unlistenChanged.then((fn) => fn());
     };
   }, []);
 
@@ -72,6 +121,7 @@ export function useTypingState() {
         status: "ready",
         fileName: fileName || "Pasted Text",
         content,
+        contentTruncated: false,
         totalChars: content.length,
         currentChar: 0,
         percent: 0,
@@ -93,6 +143,7 @@ export function useTypingState() {
       setState((prev) => ({
         ...prev,
         content,
+        contentTruncated: false,
         totalChars: content.length,
         currentChar: 0,
         percent: 0,
@@ -108,6 +159,11 @@ export function useTypingState() {
 
   const start = useCallback(async () => {
     try {
+      const missing = await commands.getMissingVariables();
+      if (missing.length > 0) {
+        setMissingVariables(missing);
+        return;
+      }
       await commands.startTyping();
     } catch (error) {
       setState((prev) => ({
@@ -118,6 +174,35 @@ export function useTypingState() {
     }
   }, []);
 
+  const typeClipboard = useCallback(async () => {
+    try {
+      await commands.typeClipboard();
+    } catch (error) {
+      setState((prev) => ({
+        ...prev,
+        status: "error",
+        errorMessage: String(error),
+      }));
+    }
+  }, []);
+
+  const startWithVariables = useCallback(async (values: Record<string, string>) => {
+    setMissingVariables([]);
+    try {
+      await commands.startTyping(values);
+    } catch (error) {
+      setState((prev) => ({
+        ...prev,
+        status: "error",
+        errorMessage: String(error),
+      }));
+    }
+  }, []);
+
+  const cancelVariables = useCallback(() => {
+    setMissingVariables([]);
+  }, []);
+
   const stop = useCallback(async () => {
     try {
       await commands.stopTyping();
@@ -155,9 +240,13 @@ export function useTypingState() {
   return {
     state,
     countdown,
+    missingVariables,
     loadContent,
     updateContent,
     start,
+    startWithVariables,
+    cancelVariables,
+    typeClipboard,
     stop,
     pause,
     resume,
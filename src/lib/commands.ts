@@ -1,12 +1,44 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Config, FileInfo } from "./types";
+import type {
+  Calibration,
+  Config,
+  FileInfo,
+  Hotkeys,
+  LogFormat,
+  Normalization,
+  Normalized,
+  QueueItem,
+  RecordedKey,
+  SessionReport,
+  StartTrigger,
+} from "./types";
 
 export async function loadFile(path: string): Promise<FileInfo> {
   return invoke<FileInfo>("load_file", { path });
 }
 
-export async function startTyping(): Promise<void> {
-  return invoke("start_typing");
+// Load the clipboard text and start typing it
+export async function typeClipboard(): Promise<FileInfo> {
+  return invoke<FileInfo>("type_clipboard");
+}
+
+export async function previewNormalization(
+  content: string,
+  options?: Normalization
+): Promise<Normalized> {
+  return invoke<Normalized>("preview_normalization", { content, options: options ?? null });
+}
+
+export async function startTyping(variables?: Record<string, string>): Promise<void> {
+  return invoke("start_typing", { variables: variables ?? null });
+}
+
+export async function getMissingVariables(): Promise<string[]> {
+  return invoke<string[]>("get_missing_variables");
+}
+
+export async function setTemplateVariables(variables: Record<string, string>): Promise<void> {
+  return invoke("set_template_variables", { variables });
 }
 
 export async function stopTyping(): Promise<void> {
@@ -44,3 +76,85 @@ export async function setFileContent(
 ): Promise<void> {
   return invoke("set_file_content", { content, fileName });
 }
+
+export async function getHotkeys(): Promise<Hotkeys> {
+  return invoke<Hotkeys>("get_hotkeys");
+}
+
+export async function getHotkeyError(): Promise<string | null> {
+  return invoke<string | null>("get_hotkey_error");
+}
+
+export async function setHotkeys(hotkeys: Hotkeys): Promise<void> {
+  return invoke("set_hotkeys", { hotkeys });
+}
+
+export async function abortTyping(undo: boolean): Promise<void> {
+  return invoke("abort_typing", { undo });
+}
+
+export async function speedUp(): Promise<number> {
+  return invoke<number>("speed_up");
+}
+
+export async function speedDown(): Promise<number> {
+  return invoke<number>("speed_down");
+}
+
+export async function enqueueContent(
+  content: string,
+  name: string,
+  options: { profile?: string; config?: Config; trigger?: StartTrigger } = {}
+): Promise<QueueItem> {
+  return invoke<QueueItem>("enqueue_content", { content, name, ...options });
+}
+
+export async function enqueueFile(
+  path: string,
+  options: { profile?: string; config?: Config; trigger?: StartTrigger } = {}
+): Promise<QueueItem> {
+  return invoke<QueueItem>("enqueue_file", { path, ...options });
+}
+
+export async function getQueue(): Promise<QueueItem[]> {
+  return invoke<QueueItem[]>("get_queue");
+}
+
+export async function removeQueueItem(id: number): Promise<void> {
+  return invoke("remove_queue_item", { id });
+}
+
+export async function moveQueueItem(id: number, index: number): Promise<void> {
+  return invoke("move_queue_item", { id, index });
+}
+
+export async function clearQueue(): Promise<void> {
+  return invoke("clear_queue");
+}
+
+export async function continueQueue(): Promise<void> {
+  return invoke("continue_queue");
+}
+
+export async function getSessionReport(): Promise<SessionReport | null> {
+  return invoke<SessionReport | null>("get_session_report");
+}
+
+export async function exportKeystrokeLog(path: string, format: LogFormat): Promise<number> {
+  return invoke<number>("export_keystroke_log", { path, format });
+}
+
+export async function calibrateFromKeys(
+  keys: RecordedKey[],
+  reference?: string
+): Promise<Calibration> {
+  return invoke<Calibration>("calibrate_from_keys", { keys, reference });
+}
+
+export async function calibrateFromFile(path: string, reference?: string): Promise<Calibration> {
+  return invoke<Calibration>("calibrate_from_file", { path, reference });
+}
+
+export async function getLanguages(): Promise<string[]> {
+  return invoke<string[]>("get_languages");
+}
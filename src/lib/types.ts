@@ -3,8 +3,11 @@ export type TypingStatus =
   | "idle"
   | "ready"
   | "countdown"
+  | "waiting"
   | "typing"
   | "paused"
+  | "focusLost"
+  | "onBreak"
   | "done"
   | "error";
 
@@ -15,6 +18,8 @@ export interface TypingState {
   percent: number;
   fileName: string | null;
   content: string | null;
+  // Content is only a preview of a longer document
+  contentTruncated: boolean;
   errorMessage: string | null;
 }
 
@@ -22,40 +27,320 @@ export interface TypingProgress {
   current: number;
   total: number;
   percent: number;
+  wpm: number;
+  item: number;
+  items: number;
+}
+
+// What has to happen before typing starts
+export type StartTrigger =
+  | { type: "countdown"; seconds?: number | null }
+  | { type: "hotkey" }
+  | { type: "windowFocus"; title: string }
+  | { type: "at"; unixMs: number };
+
+// Source languages with a code timing model
+export type CodeLanguage =
+  | "rust"
+  | "python"
+  | "javaScript"
+  | "typeScript"
+  | "c"
+  | "cpp"
+  | "java"
+  | "kotlin"
+  | "cSharp"
+  | "go"
+  | "shell";
+
+// Content queue
+
+export interface QueueItem {
+  id: number;
+  name: string;
+  charCount: number;
+  codeLanguage: CodeLanguage | null;
+  profile: string | null;
+  config: Config | null;
+  trigger: StartTrigger | null;
+  variables: string[];
+}
+
+// Shape of the random spread applied to keystroke intervals and pauses
+export type IntervalDistribution =
+  | { family: "normal" }
+  | { family: "logNormal" }
+  | { family: "exGaussian"; tail?: number }
+  | { family: "gamma" };
+
+// Warmup at the start of a session and fatigue over long ones
+export type Curve = "linear" | "exponential" | "sigmoid";
+
+export interface FatigueModel {
+  warmupSlowdown: number;
+  warmupSecs: number;
+  warmupCurve: Curve;
+  fatigueSlowdown: number;
+  fatigueSecs: number;
+  fatigueCurve: Curve;
+  recoverySecs: number;
+}
+
+// Breaks during long sessions
+export interface BreakSchedule {
+  enabled: boolean;
+  everyMinutes: number;
+  minSecs: number;
+  maxSecs: number;
+  atParagraph: boolean;
+  showStatus: boolean;
+}
+
+// Clean-up applied to content when it is loaded
+export type LineEndings = "keep" | "lf" | "crlf";
+
+export type UnicodeForm = "none" | "nfc" | "nfkc";
+
+export interface Normalization {
+  lineEndings: LineEndings;
+  unicode: UnicodeForm;
+  foldQuotes: boolean;
+  foldDashes: boolean;
+  foldSpaces: boolean;
+  expandTabs: number;
+  trimTrailing: boolean;
+}
+
+export interface NormalizationReport {
+  lineEndings: number;
+  unicode: number;
+  quotes: number;
+  dashes: number;
+  spaces: number;
+  tabs: number;
+  trailing: number;
+}
+
+export interface Normalized {
+  text: string;
+  report: NormalizationReport;
+  changes: { line: number; before: string; after: string }[];
+}
+
+// How Markdown files are typed
+export type MarkdownMode = "verbatim" | "strip" | "shortcuts";
+
+// Editor shortcuts for Markdown formatting ("Ctrl+B"); null leaves it out
+export interface EditorShortcuts {
+  bold: string | null;
+  italic: string | null;
+  heading1: string | null;
+  heading2: string | null;
+  heading3: string | null;
 }
 
 // Configuration
 export interface Config {
   baseWpm: number;
   wpmVariance: number;
+  intervalDistribution: IntervalDistribution;
   mistakeRate: number;
   correctionRate: number;
   punctuationPause: number;
   paragraphPause: number;
   thinkingPauseChance: number;
   thinkingPauseDuration: number;
+  compositionPause: number;
   burstTyping: boolean;
+  wordDifficulty: boolean;
+  codeTiming: boolean;
+  fatigue: FatigueModel;
+  breaks: BreakSchedule;
+  keyHoldMs: number;
+  rollover: boolean;
+  normalization: Normalization;
+  markdown: MarkdownMode;
+  editorShortcuts: EditorShortcuts;
+  // Fill in {{name}} placeholders before typing
+  templates: boolean;
+  // Reload a file loaded with load_file when it changes on disk
+  watchFile: boolean;
+  // Largest file that can be loaded, in megabytes (0 for no limit)
+  maxFileMb: number;
+  language: string;
+  ngramFile: string | null;
   countdownSeconds: number;
+  startTrigger: StartTrigger;
+  focusGuard: boolean;
+  keystrokeLog: boolean;
 }
 
 export const DEFAULT_CONFIG: Config = {
   baseWpm: 60,
   wpmVariance: 0.3,
+  intervalDistribution: { family: "normal" },
   mistakeRate: 0.03,
   correctionRate: 0.7,
   punctuationPause: 300,
   paragraphPause: 800,
   thinkingPauseChance: 0.02,
   thinkingPauseDuration: 1500,
+  compositionPause: 500,
   burstTyping: true,
+  wordDifficulty: true,
+  codeTiming: true,
+  fatigue: {
+    warmupSlowdown: 0.35,
+    warmupSecs: 15,
+    warmupCurve: "exponential",
+    fatigueSlowdown: 0.2,
+    fatigueSecs: 5400,
+    fatigueCurve: "sigmoid",
+    recoverySecs: 600,
+  },
+  breaks: {
+    enabled: false,
+    everyMinutes: 25,
+    minSecs: 60,
+    maxSecs: 300,
+    atParagraph: true,
+    showStatus: true,
+  },
+  keyHoldMs: 0,
+  rollover: false,
+  normalization: {
+    lineEndings: "keep",
+    unicode: "none",
+    foldQuotes: false,
+    foldDashes: false,
+    foldSpaces: false,
+    expandTabs: 0,
+    trimTrailing: false,
+  },
+  markdown: "verbatim",
+  editorShortcuts: {
+    bold: "Ctrl+B",
+    italic: "Ctrl+I",
+    heading1: "Ctrl+Alt+1",
+    heading2: "Ctrl+Alt+2",
+    heading3: "Ctrl+Alt+3",
+  },
+  templates: false,
+  watchFile: false,
+  maxFileMb: 64,
+  language: "en",
+  ngramFile: null,
   countdownSeconds: 3,
+  startTrigger: { type: "countdown" },
+  focusGuard: true,
+  keystrokeLog: false,
 };
 
+// Global shortcut bindings ("Ctrl+Alt+S"); null leaves an action unbound
+export interface Hotkeys {
+  startStop: string | null;
+  pauseResume: string | null;
+  skipParagraph: string | null;
+  speedUp: string | null;
+  speedDown: string | null;
+  abort: string | null;
+  typeClipboard: string | null;
+  undoOnAbort: boolean;
+}
+
+// Statistics for a finished run
+export interface SessionReport {
+  startedAt: number;
+  durationMs: number;
+  pausedMs: number;
+  breaks: number;
+  breakMs: number;
+  chars: number;
+  keystrokes: number;
+  backspaces: number;
+  realizedWpm: number;
+  mistakes: number;
+  corrected: number;
+  mistakesByType: Record<string, { made: number; corrected: number }>;
+  pauses: { punctuationMs: number; paragraphMs: number; thinkingMs: number; compositionMs: number };
+  stopped: boolean;
+}
+
+export type LogFormat = "jsonl" | "csv";
+
+// One recorded key event (same shape as a keystroke log entry)
+export type RecordedKey =
+  | { tMs: number; kind: "type"; char: string }
+  | { tMs: number; kind: "backspace" };
+
+// Timing of an n-gram relative to regular typing
+export interface NgramFit {
+  ratio: number;
+  spread: number;
+  samples: number;
+}
+
+// Profile fitted to a recording
+export interface Calibration {
+  config: Config;
+  digraphs: Record<string, NgramFit>;
+  trigraphs: Record<string, NgramFit>;
+  report: {
+    keystrokes: number;
+    durationMs: number;
+    observedWpm: number;
+    medianIntervalMs: number;
+    observedCv: number;
+    samples: {
+      typing: number;
+      punctuation: number;
+      paragraph: number;
+      thinking: number;
+      corrections: number;
+    };
+    warnings: string[];
+  };
+}
+
+// Formats text can be extracted from
+export type SourceFormat = "text" | "html" | "rtf" | "docx" | "odt" | "pdf";
+
+export type TextEncoding = "utf8" | "utf16Le" | "utf16Be" | "latin1";
+
 // File info returned from backend
 export interface FileInfo {
   name: string;
+  // Preview of the first 100,000 characters
   content: string;
+  // Whether the document is longer than the preview
+  truncated: boolean;
   charCount: number;
+  codeLanguage: CodeLanguage | null;
+  format: SourceFormat;
+  encoding: TextEncoding | null;
+  normalized: NormalizationReport;
+  // Template variables that need a value before typing
+  variables: string[];
+}
+
+// Line summary of a content change
+export interface DiffSummary {
+  firstLine: number | null;
+  linesRemoved: number;
+  linesAdded: number;
+  charsBefore: number;
+  charsAfter: number;
+}
+
+// Payload of the content-changed event for a watched file
+export interface ContentChanged {
+  applied: boolean;
+  // Missing when the file could not be read
+  diff?: DiffSummary;
+  // Set when the new content was loaded
+  file?: FileInfo;
+  // Why it was not loaded
+  message?: string;
 }
 
 // Widget position